@@ -1,30 +1,38 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 
 use godot::{
     engine::{self, Engine, Json},
     prelude::*,
 };
 
-use crate::util::SqoreUtil;
+use crate::{
+    scene::{game_globals::Sqore, serialization::SaveDataBuilder},
+    util::SqoreUtil,
+};
 
 use super::{
+    dialog_bark::DialogBark,
     dialog_blackboard::{Blackboard, Entry},
     dialog_events::DialogEvents,
     dialog_gui::DialogGUI,
+    dialog_session::DialogSession,
     dialog_settings::DialogSettings,
     dialog_track::{DialogError, DialogTrack, Line},
 };
 
+const BLACKBOARD_SAVE_PATH: &str = "user://core/blackboard.json";
+
 #[derive(GodotClass)]
 #[class(init, base=Object)]
 pub struct SqoreDialog {
-    current_track: Option<DialogTrack>,
+    sessions: HashMap<i64, Gd<DialogSession>>,
+    next_session_id: i64,
 
     #[var]
     override_settings: Option<Gd<DialogSettings>>,
     #[var]
     pub event_bus: Option<Gd<DialogEvents>>,
-    pub gui: Option<Gd<DialogGUI>>,
+    /// The global blackboard, shared by every session
     pub blackboard: Blackboard,
 
     base: Base<Object>,
@@ -36,6 +44,8 @@ impl IObject for SqoreDialog {}
 #[godot_api]
 impl SqoreDialog {
     pub const SINGLETON_NAME: &'static str = "SqoreDialog";
+    /// The session used by `load_track_*`, which only supports one conversation at a time
+    pub const DEFAULT_SESSION_ID: i64 = 0;
 
     #[func]
     pub fn init_event_bus(&mut self) {
@@ -57,38 +67,140 @@ impl SqoreDialog {
 
     #[func]
     pub fn load_track_file(&mut self, file_path: GString) {
-        let result = DialogTrack::load_from_json(file_path.clone());
-        if let Err(err) = result.clone() {
-            Self::handle_dialog_error(err);
-        } else {
-            self.current_track = Some(result.unwrap());
-            self.load_track();
+        if let Some(track) = Self::handle_track_result(DialogTrack::load_from_json(file_path)) {
+            self.start_session(Self::DEFAULT_SESSION_ID, track, None);
         }
     }
 
     #[func]
     pub fn load_track_text(&mut self, track_text: GString) {
         let result = DialogTrack::load_from_text(track_text, "<internal text>".to_godot());
-        if let Err(err) = result.clone() {
-            Self::handle_dialog_error(err);
-        } else {
-            self.current_track = Some(result.unwrap());
-            self.load_track();
+        if let Some(track) = Self::handle_track_result(result) {
+            self.start_session(Self::DEFAULT_SESSION_ID, track, None);
         }
     }
 
     #[func]
     pub fn load_track_dict(&mut self, track_dict: Dictionary) {
         let result = DialogTrack::load_from_dict(track_dict, "<internal dict>".to_godot());
-        if let Err(err) = result.clone() {
-            Self::handle_dialog_error(err);
-        } else {
-            self.current_track = Some(result.unwrap());
-            self.load_track();
+        if let Some(track) = Self::handle_track_result(result) {
+            self.start_session(Self::DEFAULT_SESSION_ID, track, None);
         }
     }
 
+    /// Starts a new session from a track file, returning the session id (or -1 on failure). The GUI is added to `gui_parent`, or the scene root if null.
+    #[func]
+    pub fn start_session_file(&mut self, file_path: GString, gui_parent: Option<Gd<Node>>) -> i64 {
+        let Some(track) = Self::handle_track_result(DialogTrack::load_from_json(file_path)) else {
+            return -1;
+        };
+        let id = self.allocate_session_id();
+        self.start_session(id, track, gui_parent);
+        id
+    }
+
+    /// Starts a new session from track text, returning the session id (or -1 on failure)
+    #[func]
+    pub fn start_session_text(&mut self, track_text: GString, gui_parent: Option<Gd<Node>>) -> i64 {
+        let result = DialogTrack::load_from_text(track_text, "<internal text>".to_godot());
+        let Some(track) = Self::handle_track_result(result) else {
+            return -1;
+        };
+        let id = self.allocate_session_id();
+        self.start_session(id, track, gui_parent);
+        id
+    }
+
+    /// Starts a new session from a track dictionary, returning the session id (or -1 on failure)
+    #[func]
+    pub fn start_session_dict(
+        &mut self,
+        track_dict: Dictionary,
+        gui_parent: Option<Gd<Node>>,
+    ) -> i64 {
+        let result = DialogTrack::load_from_dict(track_dict, "<internal dict>".to_godot());
+        let Some(track) = Self::handle_track_result(result) else {
+            return -1;
+        };
+        let id = self.allocate_session_id();
+        self.start_session(id, track, gui_parent);
+        id
+    }
+
+    /// Closes the session's GUI immediately and releases the session, even if its GUI was already freed
+    #[func]
+    pub fn end_session(&mut self, session_id: i64) {
+        if let Some(gui) = &mut self.get_session_gui(session_id) {
+            gui.queue_free();
+        }
+        if self.sessions.remove(&session_id).is_some() {
+            self.emit_session_ended(session_id);
+        }
+    }
+
+    #[func]
+    pub fn get_session(&self, session_id: i64) -> Option<Gd<DialogSession>> {
+        self.sessions.get(&session_id).cloned()
+    }
+
+    #[func]
+    pub fn has_session(&self, session_id: i64) -> bool {
+        self.sessions.contains_key(&session_id)
+    }
+
+    #[func]
+    pub fn get_session_ids(&self) -> PackedInt64Array {
+        PackedInt64Array::from_iter(self.sessions.keys().copied())
+    }
+
+    /// The GUI of the default session
+    #[deprecated = "sessions replaced the single `gui` field, use `get_session_gui(SqoreDialog::DEFAULT_SESSION_ID)`"]
+    pub fn gui(&self) -> Option<Gd<DialogGUI>> {
+        self.get_session_gui(Self::DEFAULT_SESSION_ID)
+    }
+
+    /// Restarts the track of the default session from the beginning
+    #[deprecated = "tracks are loaded into sessions, use `load_track_*` or `start_session_*`"]
     pub fn load_track(&mut self) {
+        let Some(session) = self.sessions.get(&Self::DEFAULT_SESSION_ID).cloned() else {
+            godot_warn!("Failed to load a dialog track");
+            return;
+        };
+        let (track, gui_parent) = {
+            let bind = session.bind();
+            (bind.track.clone(), bind.gui_parent.clone())
+        };
+        if let Some(track) = track {
+            self.start_session(Self::DEFAULT_SESSION_ID, track, gui_parent);
+        }
+    }
+
+    pub fn get_session_gui(&self, session_id: i64) -> Option<Gd<DialogGUI>> {
+        let session = self.sessions.get(&session_id)?;
+        let gui = session.bind().gui.clone()?;
+        if !gui.is_instance_valid() {
+            return None;
+        }
+        Some(gui)
+    }
+
+    fn allocate_session_id(&mut self) -> i64 {
+        // 0 is reserved for the default session
+        self.next_session_id = self.next_session_id.max(Self::DEFAULT_SESSION_ID) + 1;
+        self.next_session_id
+    }
+
+    fn handle_track_result(result: Result<DialogTrack, DialogError>) -> Option<DialogTrack> {
+        match result {
+            Ok(track) => Some(track),
+            Err(err) => {
+                Self::handle_dialog_error(err);
+                None
+            }
+        }
+    }
+
+    fn start_session(&mut self, session_id: i64, track: DialogTrack, gui_parent: Option<Gd<Node>>) {
         if self.event_bus.is_none() {
             self.init_event_bus();
         }
@@ -101,42 +213,153 @@ impl SqoreDialog {
             return;
         };
 
-        // load track data
-        let Some(track) = &self.current_track else {
-            godot_warn!("Failed to load a dialog track");
+        // kill old GUI
+        if let Some(g) = self.get_session_gui(session_id).as_mut() {
+            g.queue_free()
+        }
+
+        // create and add GUI
+        let mut session = DialogSession::new_gd();
+        let mut gui = DialogGUI::new_alloc();
+        {
+            let mut bind = session.bind_mut();
+            bind.session_id = session_id;
+            bind.gui_parent = gui_parent.clone();
+            bind.gui = Some(gui.clone());
+            bind.track = Some(track.clone());
+        }
+        {
+            let mut gui_bind = gui.bind_mut();
+            gui_bind.session_id = session_id;
+            gui_bind.track = Some(VecDeque::from_iter(track.lines));
+        }
+        let mut parent = gui_parent.unwrap_or(root.upcast());
+        SqoreUtil::add_child_deferred(&mut parent, &gui.upcast());
+        self.sessions.insert(session_id, session);
+        if let Some(bus) = &mut self.event_bus {
+            bus.emit_signal(
+                StringName::from(DialogEvents::SIGNAL_SESSION_STARTED),
+                &[session_id.to_variant()],
+            );
+        }
+    }
+
+    /// Called by [DialogGUI] when it leaves the tree. Only releases the session if that GUI is still the one assigned to it
+    #[func]
+    fn on_session_gui_exited(&mut self, session_id: i64, gui_instance_id: i64) {
+        let Some(session) = self.sessions.get(&session_id) else {
             return;
         };
+        let is_current = session
+            .bind()
+            .gui
+            .as_ref()
+            .map(|gui| gui.instance_id().to_i64() == gui_instance_id)
+            .unwrap_or(true);
+        if !is_current {
+            return;
+        }
+        self.sessions.remove(&session_id);
+        self.emit_session_ended(session_id);
+    }
 
-        // kill old GUI
-        if let Some(g) = self.gui.as_mut() {
-            if g.is_instance_valid() {
-                g.queue_free()
+    fn emit_session_ended(&mut self, session_id: i64) {
+        if let Some(bus) = &mut self.event_bus {
+            bus.emit_signal(
+                StringName::from(DialogEvents::SIGNAL_SESSION_ENDED),
+                &[session_id.to_variant()],
+            );
+        }
+    }
+
+    /// Runs a closure against the blackboard with the session's locals loaded
+    fn with_session_locals<R>(
+        &mut self,
+        session_id: i64,
+        func: impl FnOnce(&mut Blackboard) -> R,
+    ) -> R {
+        let session = self.sessions.get(&session_id).cloned();
+        if let Some(session) = &mut session.clone() {
+            self.blackboard.swap_locals(&mut session.bind_mut().locals);
+        }
+        let result = func(&mut self.blackboard);
+        if let Some(session) = &mut session.clone() {
+            self.blackboard.swap_locals(&mut session.bind_mut().locals);
+        }
+        result
+    }
+
+    /// Shows a non-blocking bark over the speaker, driven by a dialog track file. Choice nodes are not supported in barks.
+    #[func]
+    pub fn bark_track_file(
+        &mut self,
+        speaker: Gd<Node3D>,
+        file_path: GString,
+    ) -> Option<Gd<DialogBark>> {
+        match DialogTrack::load_from_json(file_path) {
+            Ok(track) => Some(DialogBark::spawn(speaker, track.lines)),
+            Err(err) => {
+                Self::handle_dialog_error(err);
+                None
             }
         }
-        // self.gui.as_mut().map(|g| {
-        // });
+    }
+
+    /// Shows a single line of text as a non-blocking bark over the speaker
+    #[func]
+    pub fn bark_text(&mut self, speaker: Gd<Node3D>, text: GString) -> Gd<DialogBark> {
+        let line = Line::Text {
+            text: text.to_string(),
+            character: String::new(),
+            requires: String::new(),
+        };
+        DialogBark::spawn(speaker, vec![line])
+    }
 
-        // Creates Really Bad Panic
-        // if let Some(gui) = &mut self.gui.clone() {
-        //     if gui.is_instance_valid() {
-        //         gui.queue_free();
-        //     }
-        // }
+    /// Runs a blackboard action on behalf of a bark, returning the internal event (if any) so the bark can handle it without touching the main GUI
+    pub fn run_bark_action(&mut self, action: String) -> Option<(String, Entry)> {
+        self.blackboard.parse_action(action);
+        let event = self.blackboard.get_event();
+        self.blackboard.mark_event_handled();
+        event
+    }
 
-        // create and add GUI
-        let mut gui = DialogGUI::new_alloc();
+    /// The settings used by a session. Either the session's override settings or [SqoreDialog::get_active_settings]
+    pub fn get_session_settings(&self, session_id: i64) -> Gd<DialogSettings> {
+        if let Some(settings) = self
+            .sessions
+            .get(&session_id)
+            .and_then(|session| session.bind().override_settings.clone())
+        {
+            return settings;
+        }
+        self.get_active_settings()
+    }
 
-        SqoreUtil::add_child_deferred(&mut root.upcast(), &gui.clone().upcast());
-        gui.bind_mut().track = Some(VecDeque::from_iter(track.lines.clone()));
-        self.gui = Some(gui);
+    /// The settings currently in use. Either the override settings or the settings from the global config
+    pub fn get_active_settings(&self) -> Gd<DialogSettings> {
+        self.override_settings.clone().unwrap_or(
+            Sqore::singleton()
+                .bind()
+                .get_config()
+                .bind()
+                .get_dialog()
+                .unwrap_or(DialogSettings::new_gd()),
+        )
     }
 
     fn handle_dialog_error(err: DialogError) {
         godot_error!("DialogError: {:#?}", err);
     }
+
     #[func]
     pub fn make_choice_selection(&mut self, selection: i32) -> bool {
-        let Some(gui) = &mut self.gui else {
+        self.make_session_choice_selection(Self::DEFAULT_SESSION_ID, selection)
+    }
+
+    #[func]
+    pub fn make_session_choice_selection(&mut self, session_id: i64, selection: i32) -> bool {
+        let Some(gui) = &mut self.get_session_gui(session_id) else {
             return false;
         };
         gui.bind_mut().make_dialog_choice(selection)
@@ -172,11 +395,24 @@ impl SqoreDialog {
         self.blackboard.parse_query(query)
     }
 
+    /// Performs a blackboard action for the default session
     #[func]
     pub fn blackboard_action(&mut self, action: GString) {
-        self.blackboard.parse_action(action.to_string());
-        let Some((event_name, event_arg)) = self.blackboard.get_event() else {
-            if let Some(gui) = &mut self.gui {
+        self.blackboard_session_action(Self::DEFAULT_SESSION_ID, action);
+    }
+
+    /// Performs a blackboard action with the session's locals available, handling any internal events (such as `end` and `jump`) for that session
+    #[func]
+    pub fn blackboard_session_action(&mut self, session_id: i64, action: GString) {
+        let event = self.with_session_locals(session_id, |blackboard| {
+            blackboard.parse_action(action.to_string());
+            let event = blackboard.get_event();
+            blackboard.mark_event_handled();
+            event
+        });
+        let gui = self.get_session_gui(session_id);
+        let Some((event_name, event_arg)) = event else {
+            if let Some(gui) = &mut gui.clone() {
                 gui.bind_mut().mark_event_handled();
             }
             return;
@@ -185,7 +421,7 @@ impl SqoreDialog {
         match event_name.as_str() {
             // TODO handle events with pub const value
             "end" => {
-                let Some(gui) = &mut self.gui else {
+                let Some(gui) = &mut gui.clone() else {
                     return;
                 };
                 gui.bind_mut().update_track(VecDeque::new());
@@ -195,10 +431,16 @@ impl SqoreDialog {
                     return;
                 };
                 let index = (index.floor()) as usize;
-                let Some(gui) = &mut self.gui else {
+                let Some(gui) = &mut gui.clone() else {
                     return;
                 };
-                let n_track = self.current_track.clone().unwrap();
+                let Some(mut session) = self.sessions.get(&session_id).cloned() else {
+                    return;
+                };
+                let Some(n_track) = session.bind().track.clone() else {
+                    return;
+                };
+                session.bind_mut().current_index = index as i64;
                 let mut lines = VecDeque::from_iter(n_track.lines.iter().cloned());
                 for _ in 0..index {
                     let _ = lines.pop_front();
@@ -207,17 +449,52 @@ impl SqoreDialog {
             }
             _ => godot_error!("Unhandled internal event! event: \"{}\"", event_name),
         }
-        self.blackboard.mark_event_handled();
-        if let Some(gui) = &mut self.gui {
+        if let Some(gui) = &mut gui.clone() {
             gui.bind_mut().mark_event_handled();
         }
     }
 
+    /// Queries the blackboard with the session's locals available
+    #[func]
+    pub fn blackboard_session_query(&mut self, session_id: i64, query: GString) -> bool {
+        self.with_session_locals(session_id, |blackboard| {
+            blackboard.parse_query(query.to_string())
+        })
+    }
+
     #[func]
     pub fn blackboard_query(&mut self, query: GString) -> bool {
         self.blackboard.parse_query(query.to_string())
     }
 
+    /// Writes the global blackboard entries to `user://core/blackboard.json`. Session locals are not saved
+    #[func]
+    pub fn save_blackboard(&mut self) -> bool {
+        let mut builder = SaveDataBuilder::new_alloc();
+        let saved = {
+            let mut bind = builder.bind_mut();
+            for (key, value) in self.blackboard.to_dictionary().iter_shared() {
+                bind.set_value(GString::from_variant(&key), value);
+            }
+            bind.save(BLACKBOARD_SAVE_PATH.to_godot())
+        };
+        builder.free();
+        saved
+    }
+
+    /// Replaces the global blackboard entries with those saved by `save_blackboard`
+    #[func]
+    pub fn load_blackboard(&mut self) -> bool {
+        let Some(mut builder) = SaveDataBuilder::try_load_file(BLACKBOARD_SAVE_PATH.to_godot())
+        else {
+            return false;
+        };
+        let entries = builder.bind_mut().get_as_dict();
+        self.blackboard.load_dictionary(&entries);
+        builder.free();
+        true
+    }
+
     #[func]
     pub fn blackboard_debug_dump(&self) {
         godot_print!("{:#?}", self.blackboard);
@@ -228,6 +505,10 @@ impl SqoreDialog {
         self.blackboard.format_text(text)
     }
 
+    pub fn blackboard_session_parse(&mut self, session_id: i64, text: String) -> String {
+        self.with_session_locals(session_id, |blackboard| blackboard.format_text(text))
+    }
+
     pub fn singleton() -> Gd<SqoreDialog> {
         let Some(vol) = Engine::singleton().get_singleton(StringName::from(Self::SINGLETON_NAME))
         else {
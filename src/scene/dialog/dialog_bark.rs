@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+
+use godot::{
+    engine::{base_material_3d::BillboardMode, label_3d::DrawFlags, ILabel3D, Label3D},
+    prelude::*,
+};
+
+use crate::{
+    scene::input::{input_device, input_glyphs},
+    util::SqoreUtil,
+};
+
+use super::{
+    core_dialog::SqoreDialog, dialog_blackboard::Entry, dialog_markup, dialog_track::Line,
+};
+
+/// A world-space speech bubble that follows a speaker. Barks never block input and several can run at the same time.
+///
+/// Created through `SqoreDialog.bark_text` or `SqoreDialog.bark_track_file`. Each text line stays visible for its reading time before moving to the next line, and the bark frees itself once the track is done.
+#[derive(GodotClass)]
+#[class(init, base=Label3D)]
+pub struct DialogBark {
+    track: VecDeque<Line>,
+    lines: Vec<Line>,
+    time_remaining: f32,
+
+    base: Base<Label3D>,
+}
+
+#[godot_api]
+impl ILabel3D for DialogBark {
+    fn ready(&mut self) {
+        let settings = SqoreDialog::singleton().bind().get_active_settings();
+        let offset = settings.bind().bark_offset;
+        let font_size = settings.bind().bark_font_size as i32;
+        let mut base = self.base_mut();
+        base.set_position(offset);
+        base.set_font_size(font_size);
+        base.set_billboard_mode(BillboardMode::ENABLED);
+        base.set_draw_flag(DrawFlags::DISABLE_DEPTH_TEST, true);
+        drop(base);
+        self.show_next_line();
+    }
+
+    fn process(&mut self, delta: f64) {
+        self.time_remaining -= delta as f32;
+        if self.time_remaining <= 0f32 {
+            self.show_next_line();
+        }
+    }
+}
+
+#[godot_api]
+impl DialogBark {
+    pub const SIGNAL_BARK_FINISHED: &'static str = "bark_finished";
+
+    #[signal]
+    fn bark_finished() {}
+
+    /// Creates a bark over the speaker, replacing any bark that speaker is already showing
+    pub fn spawn(speaker: Gd<Node3D>, lines: Vec<Line>) -> Gd<DialogBark> {
+        for child in speaker.get_children().iter_shared() {
+            if let Ok(mut previous) = child.try_cast::<DialogBark>() {
+                previous.queue_free();
+            }
+        }
+        let mut bark = DialogBark::new_alloc();
+        {
+            let mut bind = bark.bind_mut();
+            bind.track = VecDeque::from_iter(lines.iter().cloned());
+            bind.lines = lines;
+        }
+        // deferred so that the bark doesn't try to access SqoreDialog while it is still bound
+        SqoreUtil::add_child_deferred(&mut speaker.upcast(), &bark.clone().upcast());
+        bark
+    }
+
+    /// Skips the current line
+    #[func]
+    pub fn skip(&mut self) {
+        self.show_next_line();
+    }
+
+    fn show_next_line(&mut self) {
+        let Some(text) = self.get_next_text_line() else {
+            self.base_mut()
+                .emit_signal(StringName::from(Self::SIGNAL_BARK_FINISHED), &[]);
+            self.base_mut().queue_free();
+            return;
+        };
+        let settings = SqoreDialog::singleton().bind().get_active_settings();
+        let wpm = settings.bind().bark_words_per_minute;
+        let min_duration = settings.bind().bark_min_duration;
+
+        const MINUTES_TO_SECONDS: f32 = 60.0;
+        let words = text.split_whitespace().count();
+        self.time_remaining =
+            ((words as f32) * wpm.powi(-1) * MINUTES_TO_SECONDS).max(min_duration);
+        self.base_mut().set_text(text.to_godot());
+    }
+
+    fn get_next_text_line(&mut self) -> Option<String> {
+        while let Some(line) = self.track.pop_front() {
+            match &line {
+                Line::Text {
+                    text,
+                    character,
+                    requires,
+                } => {
+                    let mut dialog = SqoreDialog::singleton();
+                    if !requires.is_empty()
+                        && !dialog.bind_mut().blackboard_query(requires.to_godot())
+                    {
+                        continue;
+                    }
+                    let translated: String = self.base().tr(text.into()).into();
+                    let formatted = dialog.bind().blackboard_parse(translated);
+                    let (device, style) = input_device::get_active_device();
+                    let formatted =
+                        input_glyphs::expand_action_tags(&formatted, false, device, style);
+                    let plain =
+                        dialog_markup::strip_bbcode(&dialog_markup::parse_markup(&formatted).text);
+                    if character.is_empty() {
+                        return Some(plain);
+                    }
+                    let character = dialog.bind().blackboard_parse(character.clone());
+                    return Some(format!("{}: {}", character, plain));
+                }
+                Line::Action { action } => {
+                    let event = SqoreDialog::singleton()
+                        .bind_mut()
+                        .run_bark_action(action.clone());
+                    self.handle_event(event);
+                }
+                Line::Signal { .. } => {
+                    SqoreDialog::singleton()
+                        .bind_mut()
+                        .handle_dialog_signal(&line);
+                }
+                Line::Choice { .. } => {
+                    godot_warn!("Barks do not support choice nodes, skipping: {:#?}", line);
+                }
+                Line::None => continue,
+            }
+        }
+        None
+    }
+
+    fn handle_event(&mut self, event: Option<(String, Entry)>) {
+        let Some((event_name, event_arg)) = event else {
+            return;
+        };
+        match event_name.as_str() {
+            "end" => self.track.clear(),
+            "jump" => {
+                let Entry::Number(index) = event_arg else {
+                    return;
+                };
+                let index = (index.floor()) as usize;
+                self.track = VecDeque::from_iter(self.lines.iter().skip(index).cloned());
+            }
+            _ => godot_error!("Unhandled internal event! event: \"{}\"", event_name),
+        }
+    }
+}
@@ -36,6 +36,8 @@ impl Display for Entry {
 
 pub struct Blackboard {
     entries: HashMap<String, Entry>,
+    /// entries that belong to the dialog session currently being processed. See [Blackboard::swap_locals]
+    locals: HashMap<String, Entry>,
     commands: Vec<Command>,
 }
 
@@ -43,6 +45,7 @@ impl Default for Blackboard {
     fn default() -> Self {
         let mut zelf = Self {
             entries: HashMap::new(),
+            locals: HashMap::new(),
             commands: Vec::new(),
         };
         zelf.commands.push(Command {
@@ -75,6 +78,30 @@ impl Default for Blackboard {
 }
 
 impl Blackboard {
+    /// Keys with this prefix are stored per dialog session rather than globally. e.g. `set local.visits 1`
+    pub const LOCAL_PREFIX: &'static str = "local.";
+
+    /// Swaps the currently loaded session locals with the given storage. Call once to load a session's locals and again to store them back.
+    pub fn swap_locals(&mut self, locals: &mut HashMap<String, Entry>) {
+        std::mem::swap(&mut self.locals, locals);
+    }
+
+    fn entries_for(&self, key: &str) -> &HashMap<String, Entry> {
+        if key.starts_with(Self::LOCAL_PREFIX) {
+            &self.locals
+        } else {
+            &self.entries
+        }
+    }
+
+    fn entries_for_mut(&mut self, key: &str) -> &mut HashMap<String, Entry> {
+        if key.starts_with(Self::LOCAL_PREFIX) {
+            &mut self.locals
+        } else {
+            &mut self.entries
+        }
+    }
+
     /// Parses the action string
     pub fn parse_action(&mut self, code: String) {
         if code.is_empty() {
@@ -164,15 +191,16 @@ impl Blackboard {
             godot_warn!("Failed to find valid entry for setting to \"{}\"", key);
             return;
         };
-        self.entries.insert(key.to_string(), entry.clone());
+        self.entries_for_mut(key)
+            .insert(key.to_string(), entry.clone());
     }
 
     pub fn unset(&mut self, key: &str) {
-        let _ = self.entries.remove(&key.to_string());
+        let _ = self.entries_for_mut(key).remove(&key.to_string());
     }
 
     pub fn add(&mut self, key: &str, value: &str) {
-        if !self.entries.contains_key(&key.to_string()) {
+        if !self.entries_for(key).contains_key(&key.to_string()) {
             godot_warn!("Cannot add to \"\"! Does not exist yet!");
             return;
         }
@@ -181,7 +209,7 @@ impl Blackboard {
             godot_warn!("Failed to find valid entry for setting to \"{}\"", key);
             return;
         };
-        let Some(prev) = self.entries.get(&key.to_string()) else {
+        let Some(prev) = self.entries_for(key).get(&key.to_string()) else {
             unreachable!()
         };
         let nval = match prev {
@@ -208,10 +236,10 @@ impl Blackboard {
             },
             _ => Entry::None,
         };
-        self.entries.insert(key.to_string(), nval);
+        self.entries_for_mut(key).insert(key.to_string(), nval);
     }
     pub fn sub(&mut self, key: &str, value: &str) {
-        if !self.entries.contains_key(&key.to_string()) {
+        if !self.entries_for(key).contains_key(&key.to_string()) {
             godot_warn!("Cannot add to \"\"! Does not exist yet!");
             return;
         }
@@ -220,7 +248,7 @@ impl Blackboard {
             godot_warn!("Failed to find valid entry for setting to \"{}\"", key);
             return;
         };
-        let Some(prev) = self.entries.get(&key.to_string()) else {
+        let Some(prev) = self.entries_for(key).get(&key.to_string()) else {
             unreachable!()
         };
         let nval = match prev {
@@ -237,7 +265,7 @@ impl Blackboard {
             },
             _ => Entry::None,
         };
-        self.entries.insert(key.to_string(), nval);
+        self.entries_for_mut(key).insert(key.to_string(), nval);
     }
 
     pub const EVENT_KEY: &'static str = "__event__";
@@ -297,9 +325,9 @@ impl Blackboard {
     }
     fn get_numeric_value(&self, key: &str) -> i32 {
         // load entry
-        let entry: Entry = if self.entries.contains_key(&key.to_string()) {
+        let entry: Entry = if self.entries_for(key).contains_key(&key.to_string()) {
             // from variable name
-            self.entries.get(&key.to_string()).unwrap().clone() // we should be safe to unwrap here???
+            self.entries_for(key).get(&key.to_string()).unwrap().clone() // we should be safe to unwrap here???
         } else {
             // from constant
             let mut json = Json::new_gd();
@@ -367,7 +395,7 @@ impl Blackboard {
     }
 
     pub fn get_variant_entry(&self, key: &str) -> Variant {
-        let Some(entry) = self.entries.get(key) else {
+        let Some(entry) = self.entries_for(key).get(key) else {
             godot_warn!("Entry not found \"{}\", returning nil", key);
             return Variant::nil();
         };
@@ -380,17 +408,55 @@ impl Blackboard {
     }
 
     pub fn get(&self, key: &str) -> Option<Entry> {
-        Some(self.entries.get(key)?.clone())
+        Some(self.entries_for(key).get(key)?.clone())
     }
 
     pub fn has_entry(&self, key: &str) -> bool {
-        self.entries.contains_key(key)
+        self.entries_for(key).contains_key(key)
     }
 
     pub fn has_event(&self) -> bool {
         self.has_entry(Self::EVENT_KEY)
     }
 
+    /// The global entries for saving. Session locals and pending events are left out
+    pub fn to_dictionary(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        for (key, entry) in self.entries.iter() {
+            if key == Self::EVENT_KEY || key == Self::EVENT_ARG_KEY {
+                continue;
+            }
+            match entry {
+                Entry::Number(val) => dict.set(key.as_str(), *val),
+                Entry::String(val) => dict.set(key.as_str(), val.as_str()),
+                Entry::Bool(val) => dict.set(key.as_str(), *val),
+                Entry::None => (),
+            }
+        }
+        dict
+    }
+
+    /// Replaces the global entries with ones from [Blackboard::to_dictionary]
+    pub fn load_dictionary(&mut self, dict: &Dictionary) {
+        self.entries.clear();
+        for (key, value) in dict.iter_shared() {
+            let entry = match value.get_type() {
+                VariantType::Bool => Entry::Bool(value.booleanize()),
+                VariantType::Int | VariantType::Float => Entry::Number(f32::from_variant(&value)),
+                VariantType::String => Entry::String(String::from_variant(&value)),
+                _ => {
+                    godot_warn!(
+                        "Skipping saved blackboard entry \"{}\" with unhandled value: {}",
+                        key,
+                        value
+                    );
+                    continue;
+                }
+            };
+            self.entries.insert(key.to_string(), entry);
+        }
+    }
+
     pub fn debug_print(&self) {
         let mappings: Vec<String> = self
             .entries
@@ -408,7 +474,10 @@ impl Blackboard {
 
 impl fmt::Debug for Blackboard {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_map().entries(self.entries.iter()).finish()
+        f.debug_map()
+            .entries(self.entries.iter())
+            .entries(self.locals.iter())
+            .finish()
     }
 }
 type CommandFunction = Rc<dyn Fn(&mut Blackboard, VecDeque<String>)>;
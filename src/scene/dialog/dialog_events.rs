@@ -14,6 +14,10 @@ impl DialogEvents {
     pub const SIGNAL_TRACK_STARTED: &'static str = "track_started";
     pub const SIGNAL_TRACK_ENDED: &'static str = "track_ended";
     pub const SIGNAL_TRACK_SIGNAL: &'static str = "track_signal";
+    pub const SIGNAL_TEXT_EVENT: &'static str = "text_event";
+    pub const SIGNAL_SESSION_TEXT_EVENT: &'static str = "session_text_event";
+    pub const SIGNAL_SESSION_STARTED: &'static str = "session_started";
+    pub const SIGNAL_SESSION_ENDED: &'static str = "session_ended";
 
     #[signal]
     fn track_ended(track: GString) {}
@@ -21,4 +25,14 @@ impl DialogEvents {
     fn track_signal(name: GString, args: Array<Variant>) {}
     #[signal]
     fn track_started(track: GString) {}
+    /// emitted when the text reveal reaches an `[event=name]` tag, in any session
+    #[signal]
+    fn text_event(name: GString) {}
+    /// like `text_event`, along with the session whose text was revealed
+    #[signal]
+    fn session_text_event(name: GString, session_id: i64) {}
+    #[signal]
+    fn session_started(session_id: i64) {}
+    #[signal]
+    fn session_ended(session_id: i64) {}
 }
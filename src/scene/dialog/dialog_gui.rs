@@ -3,20 +3,33 @@ use std::collections::VecDeque;
 use godot::{
     engine::{
         control::{LayoutPreset, SizeFlags},
+        input::MouseMode,
         object::ConnectFlags,
         tween::{EaseType, TransitionType},
-        Button, CanvasLayer, Control, HSeparator, ICanvasLayer, InputEvent, Label, MarginContainer,
-        PanelContainer, RichTextLabel, Tween, VBoxContainer,
+        Button, CanvasLayer, Control, HSeparator, ICanvasLayer, InputEvent, InputEventKey,
+        InputMap, Label, MarginContainer, PanelContainer, RichTextLabel, Tween, VBoxContainer,
     },
     obj::EngineEnum,
     prelude::*,
 };
 
-use crate::{scene::game_globals::Sqore, util::SqoreUtil};
+use crate::{
+    scene::{
+        input::{
+            haptics_manager::HapticsManager,
+            input_context::{InputContext, InputContextStack},
+            input_device, input_glyphs,
+            mouse_capture::MouseCaptureStack,
+        },
+        utility_nodes::gui_interact::GuiInteract,
+    },
+    util::SqoreUtil,
+};
 
 use super::{
     core_dialog::SqoreDialog,
     dialog_events::DialogEvents,
+    dialog_markup::{self, TextMarker},
     dialog_settings::{DialogAlign, DialogSettings, EEaseType, ETransType},
     dialog_track::{ChoiceOptionEntry, Line},
 };
@@ -31,16 +44,35 @@ enum DialogState {
     Pending,
 }
 
+/// Tracks the character-by-character reveal of the current line
+#[derive(Debug, Default)]
+struct TextReveal {
+    markers: VecDeque<(usize, TextMarker)>,
+    visible: f32,
+    total: usize,
+    characters_per_second: f32,
+    speed_scale: f32,
+    pause_remaining: f32,
+}
+
 #[derive(GodotClass)]
 #[class(init, base=CanvasLayer)]
 pub struct DialogGUI {
     tween: Option<Gd<Tween>>,
+    reveal: Option<TextReveal>,
     pub track: Option<VecDeque<Line>>,
     character_label: Option<Gd<Label>>,
     dialog_text: Option<Gd<RichTextLabel>>,
     options_root: Option<Gd<Control>>,
-    current_index: usize,
+    layout_margin: Option<Gd<MarginContainer>>,
+    layout_panel: Option<Gd<PanelContainer>>,
+    layout_panel_margin: Option<Gd<MarginContainer>>,
+    sfx_player: Option<Gd<AudioStreamPlayer>>,
     state: DialogState,
+    /// The id of the [DialogSession](super::dialog_session::DialogSession) this GUI displays
+    pub session_id: i64,
+    /// The name of the input context pushed in `ready`, popped again on exit even if the settings change in between
+    pushed_context: Option<StringName>,
 
     base: Base<CanvasLayer>,
 }
@@ -52,6 +84,12 @@ impl ICanvasLayer for DialogGUI {
     }
 
     fn ready(&mut self) {
+        self.push_input_context();
+        if self.get_settings().bind().show_mouse {
+            MouseCaptureStack::singleton()
+                .bind_mut()
+                .push_mode(self.get_mouse_key(), MouseMode::VISIBLE.ord());
+        }
         self.create_structure();
         if let Some(line) = self.get_next_text_line() {
             self.load_line(&line);
@@ -65,17 +103,31 @@ impl ICanvasLayer for DialogGUI {
     fn input(&mut self, event: Gd<InputEvent>) {
         if self.options_root.is_some() {
             // means there is a dialog choice being made
+            self.handle_choice_input(event);
             return;
         }
         let settings = self.get_settings();
-        if !event.is_action_pressed(StringName::from(settings.bind().interact_action.clone())) {
+        let interact = StringName::from(settings.bind().interact_action.clone());
+        if !InputContextStack::singleton()
+            .bind()
+            .is_event_action_pressed(event, interact)
+        {
             return;
         }
+        // the press belongs to the dialog, so interaction nodes don't also act on it
+        if let Some(mut viewport) = self.base().get_viewport() {
+            viewport.set_input_as_handled();
+        }
         let mut progress_next_node_flag = true;
+        if self.reveal.is_some() {
+            // skips the rest of the reveal, including any remaining pauses and events
+            self.finish_reveal();
+            progress_next_node_flag = false;
+        }
         if let Some(tween) = &mut self.tween {
             if tween.is_running() {
-                // forces tween to finish (should usually only run once)
-                while tween.custom_step(10f64) {}
+                // forces tween to finish
+                SqoreUtil::finish_tween(tween);
                 self.tween = None;
                 progress_next_node_flag = false;
             }
@@ -84,7 +136,8 @@ impl ICanvasLayer for DialogGUI {
             self.load_next_line();
         }
     }
-    fn process(&mut self, _delta: f64) {
+    fn process(&mut self, delta: f64) {
+        self.process_reveal(delta as f32);
         if self.state != DialogState::Pending {
             return;
         }
@@ -93,10 +146,23 @@ impl ICanvasLayer for DialogGUI {
     }
 
     fn exit_tree(&mut self) {
-        //pass
-        if let Some(event_bus) = &mut SqoreDialog::singleton().bind().get_event_bus() {
+        MouseCaptureStack::singleton()
+            .bind_mut()
+            .pop_mode(self.get_mouse_key());
+        if let Some(context) = self.pushed_context.take() {
+            InputContextStack::singleton()
+                .bind_mut()
+                .pop_context(context);
+        }
+        let mut dialog = SqoreDialog::singleton();
+        if let Some(event_bus) = &mut dialog.bind().get_event_bus() {
             event_bus.emit_signal(StringName::from(DialogEvents::SIGNAL_TRACK_ENDED), &[]);
         }
+        let gui_id = self.base().instance_id().to_i64();
+        dialog.call_deferred(
+            "on_session_gui_exited".into(),
+            &[self.session_id.to_variant(), gui_id.to_variant()],
+        );
     }
 }
 
@@ -134,6 +200,9 @@ impl DialogGUI {
         panel.add_child(panel_margin.clone().upcast());
         margin.add_child(panel.clone().upcast());
         self.base_mut().add_child(margin.clone().upcast());
+        let sfx_player = AudioStreamPlayer::new_alloc();
+        self.base_mut().add_child(sfx_player.clone().upcast());
+        self.sfx_player = Some(sfx_player);
         self.character_label = Some(label.clone());
         self.dialog_text = Some(rich_text.clone());
 
@@ -153,24 +222,16 @@ impl DialogGUI {
             .add_theme_font_size_override(StringName::from("bold_italics_font_size"), font_size);
         rich_text.add_theme_font_size_override(StringName::from("mono_font_size"), font_size);
 
-        panel.set_custom_minimum_size(Vector2 {
-            x: 360.0f32, // x size managed by container
-            y: 240.0f32, // push min size up
-        });
-
-        const PANEL_BOX: i32 = 32;
-        panel_margin.add_theme_constant_override(StringName::from("margin_bottom"), PANEL_BOX);
-        panel_margin.add_theme_constant_override(StringName::from("margin_top"), PANEL_BOX);
-        panel_margin.add_theme_constant_override(StringName::from("margin_left"), PANEL_BOX);
-        panel_margin.add_theme_constant_override(StringName::from("margin_right"), PANEL_BOX);
-
-        let margin_lr = settings.bind().dialog_align.get_margins();
-        const BOTTOM_MARGIN: i32 = 32;
-        margin.add_theme_constant_override(StringName::from("margin_left"), margin_lr.0);
-        margin.add_theme_constant_override(StringName::from("margin_right"), margin_lr.1);
-        margin.add_theme_constant_override(StringName::from("margin_bottom"), BOTTOM_MARGIN);
-        margin.set_anchors_and_offsets_preset(LayoutPreset::BOTTOM_WIDE);
-        margin.force_update_transform();
+        self.layout_margin = Some(margin.clone());
+        self.layout_panel = Some(panel);
+        self.layout_panel_margin = Some(panel_margin);
+        self.apply_layout();
+        if let Some(viewport) = &mut self.base().get_viewport() {
+            viewport.connect(
+                StringName::from("size_changed"),
+                Callable::from_object_method(&self.to_gd(), "on_viewport_size_changed"),
+            );
+        }
         /*
         	*/
         // Animation
@@ -213,7 +274,65 @@ impl DialogGUI {
             margin_pos.y.to_variant(),
             settings.bind().anim_appear_duration as f64,
         );
+        self.tween = Some(tween);
+    }
+    /// Sizes the dialog box relative to the current viewport size
+    fn apply_layout(&mut self) {
+        let settings = self.get_settings();
+        let settings = settings.bind();
+        let Some(viewport) = self.base().get_viewport() else {
+            return;
+        };
+        let view_size = viewport.get_visible_rect().size;
+        let (min, max) = (
+            settings.dialog_margin_min_pixels,
+            settings.dialog_margin_max_pixels,
+        );
+        let side = SqoreUtil::percent_to_pixels(view_size.x, settings.dialog_side_margin, min, max);
+        let edge = SqoreUtil::percent_to_pixels(view_size.x, settings.dialog_edge_margin, min, max);
+        let mut bottom =
+            SqoreUtil::percent_to_pixels(view_size.y, settings.dialog_bottom_margin, min, max);
+        let padding = SqoreUtil::percent_to_pixels(view_size.y, settings.dialog_padding, min, max);
+        let (mut left, mut right) = settings.dialog_align.get_margins(side, edge);
+        if settings.respect_safe_area {
+            let insets = SqoreUtil::get_safe_area_insets(&viewport);
+            left = left.max(insets.0.ceil() as i32);
+            right = right.max(insets.2.ceil() as i32);
+            bottom = bottom.max(insets.3.ceil() as i32);
+        }
+
+        if let Some(panel) = &mut self.layout_panel {
+            panel.set_custom_minimum_size(Vector2 {
+                x: 0f32,                                     // x size managed by container
+                y: view_size.y * settings.dialog_min_height, // push min size up
+            });
+        }
+        if let Some(panel_margin) = &mut self.layout_panel_margin {
+            for side in ["margin_bottom", "margin_top", "margin_left", "margin_right"] {
+                panel_margin.add_theme_constant_override(StringName::from(side), padding);
+            }
+        }
+        let Some(margin) = &mut self.layout_margin else {
+            return;
+        };
+        margin.add_theme_constant_override(StringName::from("margin_left"), left);
+        margin.add_theme_constant_override(StringName::from("margin_right"), right);
+        margin.add_theme_constant_override(StringName::from("margin_bottom"), bottom);
+        margin.set_anchors_and_offsets_preset(LayoutPreset::BOTTOM_WIDE);
+        margin.force_update_transform();
     }
+
+    #[func]
+    fn on_viewport_size_changed(&mut self) {
+        if let Some(tween) = &mut self.tween {
+            if tween.is_running() {
+                // finish any running animation so it doesn't fight with the new layout
+                SqoreUtil::finish_tween(tween);
+            }
+        }
+        self.apply_layout();
+    }
+
     /*
         pub struct ChoiceOptionEntry {
             text: String,
@@ -225,12 +344,13 @@ impl DialogGUI {
         let mut root = VBoxContainer::new_alloc();
         self.to_gd().add_child(root.clone().upcast());
         self.options_root = Some(root.clone().upcast());
-        let mut is_first = self.get_settings().bind().auto_focus_choice_buttons;
+        let settings = self.get_settings();
+        let mut is_first = settings.bind().auto_focus_choice_buttons;
         for (index, option) in choices.iter().enumerate() {
             if !option.requires.is_empty()
                 && !SqoreDialog::singleton()
                     .bind_mut()
-                    .blackboard_query(option.requires.clone().into())
+                    .blackboard_session_query(self.session_id, option.requires.clone().into())
             {
                 // does not meet conditions
                 continue;
@@ -239,6 +359,7 @@ impl DialogGUI {
             root.add_child(button.clone().upcast());
             button.set_text(self.parse_text(&option.text).into());
             let action = option.action.clone();
+            let session_id = self.session_id;
             if !action.is_empty() {
                 self.state = DialogState::Pending;
             }
@@ -250,8 +371,10 @@ impl DialogGUI {
                         move |_| {
                             SqoreDialog::singleton()
                                 .bind_mut()
-                                .blackboard_action(action.clone().into());
-                            let Some(gui) = &mut SqoreDialog::singleton().bind().gui.clone() else {
+                                .blackboard_session_action(session_id, action.clone().into());
+                            let Some(gui) =
+                                &mut SqoreDialog::singleton().bind().get_session_gui(session_id)
+                            else {
                                 godot_error!(
                                     "Failed to find instance of the CoreDialog's DialogGUI"
                                 );
@@ -264,12 +387,24 @@ impl DialogGUI {
                 )
                 .flags(ConnectFlags::DEFERRED.ord() as u32)
                 .done();
+            button.connect(
+                "pressed".into(),
+                Callable::from_object_method(&self.to_gd(), "on_choice_pressed"),
+            );
+            // handles hover focus and hover sfx
+            let mut interact = GuiInteract::new_alloc();
+            {
+                let mut bind = interact.bind_mut();
+                bind.set_focus_on_hover(settings.bind().choice_focus_on_hover);
+                bind.set_hover_sfx(settings.bind().choice_hover_sfx.clone());
+            }
+            button.add_child(interact.upcast());
             if is_first {
                 button.grab_focus();
                 is_first = false;
             }
         }
-        let align = self.get_settings().bind().choice_buttons_align.clone();
+        let align = settings.bind().choice_buttons_align.clone();
         root.set_anchors_and_offsets_preset(match align {
             DialogAlign::Left => LayoutPreset::CENTER_LEFT,
             DialogAlign::Right => LayoutPreset::CENTER_RIGHT,
@@ -288,10 +423,7 @@ impl DialogGUI {
             } => {
                 let parsed_text = self.parse_text(text);
                 let parsed_char = self.parse_text(character);
-
-                if let Some(dialog_text) = self.dialog_text.as_mut() {
-                    dialog_text.set_text(parsed_text.to_godot());
-                }
+                self.set_dialog_text(&parsed_text);
 
                 if let Some(character_label) = &mut self.character_label {
                     character_label.set_text(parsed_char.to_godot());
@@ -304,9 +436,7 @@ impl DialogGUI {
             } => {
                 let parsed_prompt = self.parse_text(prompt);
                 let parsed_char = self.parse_text(character);
-                if let Some(dialog_text) = &mut self.dialog_text {
-                    dialog_text.set_text(parsed_prompt.to_godot());
-                }
+                self.set_dialog_text(&parsed_prompt);
                 if let Some(character_label) = &mut self.character_label {
                     character_label.set_text(parsed_char.to_godot());
                 }
@@ -316,30 +446,123 @@ impl DialogGUI {
                 godot_warn!("DialogGUI does not handle Line of type: {:#?}", track);
             }
         }
+    }
+
+    /// Strips the inline markup from the text and starts revealing it character by character
+    fn set_dialog_text(&mut self, parsed_text: &str) {
+        const MINUTES_TO_SECONDS: f32 = 60.0;
+        let (device, style) = input_device::get_active_device();
+        let expanded = input_glyphs::expand_action_tags(parsed_text, true, device, style);
+        let markup = dialog_markup::parse_markup(&expanded);
         let wpm = self.get_settings().bind().words_per_minute;
-        let mut tween = self.get_text_tween(EEaseType::InOut, ETransType::Linear);
         let Some(text) = &mut self.dialog_text else {
             return;
         };
+        text.set_text(markup.text.to_godot());
+        text.set_visible_characters(0);
 
-        const ZERO_TEXT: f32 = 0f32;
-        const FULL_TEXT: f32 = 1f32;
-        const MINUTES_TO_SECONDS: f32 = 60.0;
-
+        let total = text.get_total_character_count().max(0) as usize;
         let words = text
-            .get_text()
-            .clone()
+            .get_parsed_text()
             .to_string()
             .split_whitespace()
             .count();
+        // keeps the same overall timing as revealing the line at `words_per_minute`
         let duration_seconds = (words as f32) * wpm.powi(-1) * MINUTES_TO_SECONDS;
-        text.set_visible_ratio(ZERO_TEXT);
-        tween.tween_property(
-            text.clone().upcast(),
-            NodePath::from("visible_ratio"),
-            FULL_TEXT.to_variant(),
-            duration_seconds as f64,
-        );
+        let characters_per_second = if duration_seconds > 0f32 {
+            total as f32 / duration_seconds
+        } else {
+            f32::MAX
+        };
+        self.reveal = Some(TextReveal {
+            markers: VecDeque::from(markup.markers),
+            visible: 0f32,
+            total,
+            characters_per_second,
+            speed_scale: 1f32,
+            pause_remaining: 0f32,
+        });
+    }
+
+    fn process_reveal(&mut self, delta: f32) {
+        let Some(reveal) = &mut self.reveal else {
+            return;
+        };
+        let mut events = Vec::new();
+        let mut rumbles = Vec::new();
+        let mut remaining = delta;
+        let mut finished = false;
+        while remaining > 0f32 {
+            if reveal.pause_remaining > 0f32 {
+                let used = reveal.pause_remaining.min(remaining);
+                reveal.pause_remaining -= used;
+                remaining -= used;
+                continue;
+            }
+            let rate = reveal.characters_per_second * reveal.speed_scale;
+            let target = reveal
+                .markers
+                .front()
+                .map(|(index, _)| *index)
+                .unwrap_or(reveal.total) as f32;
+            let step = remaining * rate;
+            if reveal.visible + step < target {
+                reveal.visible += step;
+                break;
+            }
+            // reached the next marker (or the end of the text) this frame
+            remaining -= (target - reveal.visible) / rate;
+            reveal.visible = target;
+            let Some((_, marker)) = reveal.markers.pop_front() else {
+                finished = true;
+                break;
+            };
+            match marker {
+                TextMarker::Pause(seconds) => reveal.pause_remaining = seconds.max(0f32),
+                TextMarker::Speed(scale) => {
+                    if scale > 0f32 {
+                        reveal.speed_scale = scale;
+                    } else {
+                        godot_warn!("Dialog [speed={}] must be greater than zero", scale);
+                    }
+                }
+                TextMarker::Event(name) => events.push(name),
+                TextMarker::Rumble(name) => rumbles.push(name),
+            }
+        }
+        let visible = reveal.visible.floor() as i32;
+        if finished {
+            self.finish_reveal();
+        } else if let Some(text) = &mut self.dialog_text {
+            text.set_visible_characters(visible);
+        }
+        for name in events {
+            self.emit_text_event(name);
+        }
+        for name in rumbles {
+            HapticsManager::singleton().bind_mut().play(name.into());
+        }
+    }
+
+    fn finish_reveal(&mut self) {
+        const ALL_CHARACTERS: i32 = -1;
+        self.reveal = None;
+        if let Some(text) = &mut self.dialog_text {
+            text.set_visible_characters(ALL_CHARACTERS);
+        }
+    }
+
+    fn emit_text_event(&self, name: String) {
+        if let Some(event_bus) = &mut SqoreDialog::singleton().bind().get_event_bus() {
+            event_bus.emit_signal(
+                StringName::from(DialogEvents::SIGNAL_TEXT_EVENT),
+                &[name.to_variant()],
+            );
+            event_bus.emit_signal(
+                StringName::from(DialogEvents::SIGNAL_SESSION_TEXT_EVENT),
+                &[name.to_variant(), self.session_id.to_variant()],
+            );
+        }
     }
 
     fn get_next_text_line(&mut self) -> Option<Line> {
@@ -348,6 +571,10 @@ impl DialogGUI {
         };
         #[allow(unused_variables)]
         while let Some(line) = track.pop_front() {
+            if let Some(session) = &mut SqoreDialog::singleton().bind().get_session(self.session_id)
+            {
+                session.bind_mut().current_index += 1;
+            }
             let result: Option<Line> = match line.clone() {
                 Line::Text {
                     text,
@@ -357,7 +584,7 @@ impl DialogGUI {
                     if requires.is_empty()
                         || SqoreDialog::singleton()
                             .bind_mut()
-                            .blackboard_query(requires.to_godot())
+                            .blackboard_session_query(self.session_id, requires.to_godot())
                     {
                         Some(line)
                     } else {
@@ -372,8 +599,10 @@ impl DialogGUI {
                 Line::Action { action } => {
                     self.state = DialogState::Pending;
                     godot_print!("Pending processing for action {}", action);
-                    SqoreDialog::singleton()
-                        .call_deferred("blackboard_action".into(), &[action.to_variant()]);
+                    SqoreDialog::singleton().call_deferred(
+                        "blackboard_session_action".into(),
+                        &[self.session_id.to_variant(), action.to_variant()],
+                    );
                     return None; // force break to allow processing events
                 }
                 Line::Signal { name, args } => {
@@ -409,18 +638,52 @@ impl DialogGUI {
         tween
     }
 
+    fn push_input_context(&mut self) {
+        let settings = self.get_settings();
+        let settings = settings.bind();
+        if settings.input_context.is_empty() {
+            return;
+        }
+        let context =
+            InputContextStack::find_configured(&settings.input_context).unwrap_or_else(|| {
+                let mut context = InputContext::new_gd();
+                {
+                    let mut bind = context.bind_mut();
+                    bind.name = settings.input_context.clone();
+                    // above gameplay, but below anything a game would put on top of dialog like a pause menu
+                    bind.priority = 100;
+                    bind.exclusive = true;
+                    for action in [
+                        &settings.interact_action,
+                        &settings.choice_previous_action,
+                        &settings.choice_next_action,
+                        &settings.choice_accept_action,
+                    ] {
+                        bind.actions.push(StringName::from(action));
+                    }
+                    for action in InputMap::singleton().get_actions().iter_shared() {
+                        if action.to_string().starts_with("ui_") {
+                            bind.actions.push(action);
+                        }
+                    }
+                }
+                context
+            });
+        self.pushed_context = Some(context.bind().name.clone());
+        InputContextStack::singleton()
+            .bind_mut()
+            .push_context_resource(context);
+    }
+
+    /// The key of the mouse mode request, one per session
+    fn get_mouse_key(&self) -> StringName {
+        StringName::from(format!("dialog_{}", self.session_id).as_str())
+    }
+
     fn get_settings(&self) -> Gd<DialogSettings> {
         SqoreDialog::singleton()
             .bind()
-            .get_override_settings()
-            .unwrap_or(
-                Sqore::singleton()
-                    .bind()
-                    .get_config()
-                    .bind()
-                    .get_dialog()
-                    .unwrap_or(DialogSettings::new_gd()),
-            )
+            .get_session_settings(self.session_id)
     }
 
     fn dialog_choice_was_made_callable(&mut self, _index: usize) {
@@ -443,10 +706,99 @@ impl DialogGUI {
         let Ok(child) = &mut child.try_cast::<Button>() else {
             return false;
         };
-        child.set_deferred("pressed".into(), true.to_variant());
+        child.call_deferred("emit_signal".into(), &["pressed".to_variant()]);
         true
     }
 
+    /// The choice buttons currently shown, in display order
+    fn get_choice_buttons(&self) -> Vec<Gd<Button>> {
+        let Some(root) = &self.options_root else {
+            return Vec::new();
+        };
+        root.get_children()
+            .iter_shared()
+            .filter_map(|child| child.try_cast::<Button>().ok())
+            .filter(|button| !button.is_queued_for_deletion())
+            .collect()
+    }
+
+    fn handle_choice_input(&mut self, event: Gd<InputEvent>) {
+        let settings = self.get_settings();
+        let buttons = self.get_choice_buttons();
+        if buttons.is_empty() {
+            return;
+        }
+        let focused = buttons.iter().position(|button| button.has_focus());
+        let stack = InputContextStack::singleton();
+        let stack = stack.bind();
+        let is_pressed = |action: &GString| {
+            stack.is_event_action_pressed(event.clone(), StringName::from(action))
+        };
+        let mut handled = true;
+        if let Some(index) = Self::get_number_key_choice(&settings, &event) {
+            self.make_dialog_choice(index);
+        } else if is_pressed(&settings.bind().choice_next_action) {
+            let next = focused
+                .map(|index| (index + 1) % buttons.len())
+                .unwrap_or(0);
+            buttons[next].clone().grab_focus();
+        } else if is_pressed(&settings.bind().choice_previous_action) {
+            let previous = focused
+                .map(|index| (index + buttons.len() - 1) % buttons.len())
+                .unwrap_or(buttons.len() - 1);
+            buttons[previous].clone().grab_focus();
+        } else if is_pressed(&settings.bind().choice_accept_action) {
+            match focused {
+                Some(index) => {
+                    self.make_dialog_choice(index as i32);
+                }
+                None => buttons[0].clone().grab_focus(),
+            }
+        } else {
+            handled = false;
+        }
+        if handled {
+            if let Some(viewport) = &mut self.base().get_viewport() {
+                viewport.set_input_as_handled();
+            }
+        }
+    }
+
+    fn get_number_key_choice(settings: &Gd<DialogSettings>, event: &Gd<InputEvent>) -> Option<i32> {
+        const KEY_1: i32 = 49;
+        const KEY_9: i32 = 57;
+        if !settings.bind().choice_number_keys {
+            return None;
+        }
+        // number keys accept a choice, so they follow the accept action being blocked by other contexts
+        let accept = StringName::from(settings.bind().choice_accept_action.clone());
+        if !InputContextStack::singleton().bind().is_action_live(accept) {
+            return None;
+        }
+        let key = event.clone().try_cast::<InputEventKey>().ok()?;
+        if !key.is_pressed() || key.is_echo() {
+            return None;
+        }
+        // physical keys so the number row works regardless of keyboard layout
+        let keycode = key.get_physical_keycode().ord();
+        if !(KEY_1..=KEY_9).contains(&keycode) {
+            return None;
+        }
+        Some(keycode - KEY_1)
+    }
+
+    #[func]
+    fn on_choice_pressed(&mut self) {
+        let Some(sfx) = self.get_settings().bind().choice_select_sfx.clone() else {
+            return;
+        };
+        if let Some(player) = &mut self.sfx_player {
+            player.stop();
+            player.set_stream(sfx);
+            player.play();
+        }
+    }
+
     fn load_next_line(&mut self) {
         if self.state == DialogState::Pending {
             return;
@@ -485,6 +837,8 @@ impl DialogGUI {
 
     fn parse_text(&self, in_text: &String) -> String {
         let trans = self.base().tr(in_text.into()).into();
-        SqoreDialog::singleton().bind().blackboard_parse(trans)
+        SqoreDialog::singleton()
+            .bind_mut()
+            .blackboard_session_parse(self.session_id, trans)
     }
 }
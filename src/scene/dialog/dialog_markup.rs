@@ -0,0 +1,162 @@
+//! Inline markup for dialog text.
+//!
+//! Dialog lines can contain a few extra tags alongside regular BBCode. These are stripped out before the text ever reaches the [RichTextLabel](godot::engine::RichTextLabel) and instead become markers at a specific visible character.
+//!
+//! - `[pause=0.5]` waits for the given number of seconds before revealing more text
+//! - `[speed=2]` multiplies the reveal speed (`[speed=1]` returns to normal)
+//! - `[event=name]` emits `DialogEvents.text_event` with the given name when the reveal reaches it
+//! - `[rumble=name]` plays the named rumble preset through the [HapticsManager](crate::scene::input::haptics_manager::HapticsManager) when the reveal reaches it
+//! - `[action=name]` shows the input bound to the action, see [input_glyphs](crate::scene::input::input_glyphs). This one is expanded before parsing
+//!
+//! Any other tag is assumed to be BBCode and is passed through untouched.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextMarker {
+    Pause(f32),
+    Speed(f32),
+    Event(String),
+    Rumble(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MarkupText {
+    /// The BBCode text with all Sqore tags removed
+    pub text: String,
+    /// Markers paired with the visible character index they trigger at
+    pub markers: Vec<(usize, TextMarker)>,
+}
+
+const TAG_PAUSE: &str = "pause";
+const TAG_SPEED: &str = "speed";
+const TAG_EVENT: &str = "event";
+const TAG_RUMBLE: &str = "rumble";
+/// BBCode tags whose contents are displayed as a single visible character
+const SINGLE_CHARACTER_TAGS: [&str; 1] = ["img"];
+
+pub fn parse_markup(source: &str) -> MarkupText {
+    let mut result = MarkupText {
+        text: String::with_capacity(source.len()),
+        markers: Vec::new(),
+    };
+    let mut visible_index = 0usize;
+    let mut remaining = source;
+    while let Some(open) = remaining.find('[') {
+        let (pre, post) = remaining.split_at(open);
+        result.text += pre;
+        visible_index += pre.chars().count();
+        let Some(close) = post.find(']') else {
+            // unterminated tag, treat it as plain text
+            remaining = post;
+            break;
+        };
+        let tag = &post[1..close];
+        let after = &post[close + 1..];
+        if let Some(marker) = parse_marker(tag) {
+            result.markers.push((visible_index, marker));
+            remaining = after;
+            continue;
+        }
+        // BBCode tag, keep it but it doesn't count towards visible characters
+        result.text += &post[..=close];
+        remaining = after;
+        let tag_name = tag.split(['=', ' ']).next().unwrap_or_default();
+        if SINGLE_CHARACTER_TAGS.contains(&tag_name) {
+            let closing = format!("[/{}]", tag_name);
+            if let Some(end) = remaining.find(closing.as_str()) {
+                result.text += &remaining[..end + closing.len()];
+                remaining = &remaining[end + closing.len()..];
+                visible_index += 1;
+            }
+        }
+    }
+    result.text += remaining;
+    result
+}
+
+fn parse_marker(tag: &str) -> Option<TextMarker> {
+    let (key, value) = tag.split_once('=')?;
+    let value = value.trim();
+    match key.trim().to_lowercase().as_str() {
+        TAG_PAUSE => value.parse::<f32>().ok().map(TextMarker::Pause),
+        TAG_SPEED => value.parse::<f32>().ok().map(TextMarker::Speed),
+        TAG_EVENT => Some(TextMarker::Event(value.to_string())),
+        TAG_RUMBLE => Some(TextMarker::Rumble(value.to_string())),
+        _ => None,
+    }
+}
+
+/// Removes all BBCode tags, for displays that can't render them (such as [Label3D](godot::engine::Label3D))
+pub fn strip_bbcode(source: &str) -> String {
+    let mut buffer = String::with_capacity(source.len());
+    let mut remaining = source;
+    while let Some(open) = remaining.find('[') {
+        let (pre, post) = remaining.split_at(open);
+        buffer += pre;
+        let Some(close) = post.find(']') else {
+            remaining = post;
+            break;
+        };
+        remaining = &post[close + 1..];
+    }
+    buffer += remaining;
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markers_are_removed_and_indexed_by_visible_character() {
+        let parsed = parse_markup("Hi[pause=0.5] there[speed=2]![event=wave]");
+        assert_eq!(parsed.text, "Hi there!");
+        assert_eq!(
+            parsed.markers,
+            vec![
+                (2, TextMarker::Pause(0.5)),
+                (8, TextMarker::Speed(2.0)),
+                (9, TextMarker::Event("wave".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_bbcode_is_kept_and_not_counted() {
+        let parsed = parse_markup("[b][i]ab[/i][/b][event=x]c");
+        assert_eq!(parsed.text, "[b][i]ab[/i][/b]c");
+        assert_eq!(
+            parsed.markers,
+            vec![(2, TextMarker::Event("x".to_string()))]
+        );
+    }
+
+    #[test]
+    fn unknown_tags_pass_through_as_bbcode() {
+        let parsed = parse_markup("[shake rate=20]a[/shake][pause=oops]b");
+        assert_eq!(parsed.text, "[shake rate=20]a[/shake][pause=oops]b");
+        assert!(parsed.markers.is_empty());
+    }
+
+    #[test]
+    fn images_count_as_one_character() {
+        let parsed = parse_markup("a[img=16x16]res://icon.png[/img][pause=1]b");
+        assert_eq!(parsed.text, "a[img=16x16]res://icon.png[/img]b");
+        assert_eq!(parsed.markers, vec![(2, TextMarker::Pause(1.0))]);
+    }
+
+    #[test]
+    fn unterminated_tags_are_plain_text() {
+        let parsed = parse_markup("a[pause=1");
+        assert_eq!(parsed.text, "a[pause=1");
+        assert!(parsed.markers.is_empty());
+    }
+
+    #[test]
+    fn strip_bbcode_removes_every_tag() {
+        assert_eq!(
+            strip_bbcode("[b]bold[/b] [color=red]red[/color]"),
+            "bold red"
+        );
+        assert_eq!(strip_bbcode("open [tag"), "open [tag");
+    }
+}
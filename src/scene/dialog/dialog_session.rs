@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+
+use godot::prelude::*;
+
+use super::{
+    dialog_blackboard::Entry, dialog_gui::DialogGUI, dialog_settings::DialogSettings,
+    dialog_track::DialogTrack,
+};
+
+/// A single running conversation. Owns the track being played, the GUI displaying it, and any session-local blackboard entries (keys prefixed with `local.`).
+///
+/// Sessions are created and managed by `SqoreDialog`, which shares one global blackboard between all sessions.
+#[derive(GodotClass)]
+#[class(init, base=RefCounted)]
+pub struct DialogSession {
+    #[var]
+    pub session_id: i64,
+    /// Where the GUI is added. Defaults to the scene tree root, but can be any node (such as a SubViewport for split-screen)
+    #[var]
+    pub gui_parent: Option<Gd<Node>>,
+    /// Settings used only by this session. Falls back to the settings of `SqoreDialog`
+    #[var]
+    pub override_settings: Option<Gd<DialogSettings>>,
+    #[var]
+    pub gui: Option<Gd<DialogGUI>>,
+    /// Index of the next line to take from the track
+    #[var]
+    pub current_index: i64,
+
+    pub track: Option<DialogTrack>,
+    pub locals: HashMap<String, Entry>,
+
+    base: Base<RefCounted>,
+}
+
+#[godot_api]
+impl DialogSession {
+    /// Whether this session still has a GUI showing its track
+    #[func]
+    pub fn is_running(&self) -> bool {
+        self.gui
+            .as_ref()
+            .map(|gui| gui.is_instance_valid())
+            .unwrap_or(false)
+    }
+}
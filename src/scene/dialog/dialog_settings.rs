@@ -1,4 +1,7 @@
-use godot::{engine::LabelSettings, prelude::*};
+use godot::{
+    engine::{AudioStream, LabelSettings},
+    prelude::*,
+};
 
 #[derive(GodotConvert, Var, Default, Export, Clone)]
 #[godot(via=i64)]
@@ -11,15 +14,13 @@ pub enum DialogAlign {
 }
 
 impl DialogAlign {
-    pub fn get_margins(&self) -> (i32, i32) {
+    /// Picks the (left, right) margins given the pixel sizes of the wide "side" margin and the narrow "edge" margin
+    pub fn get_margins(&self, side: i32, edge: i32) -> (i32, i32) {
         match self {
-            // icky hardcoded values :P
-            // I really want 'margin-left: 15%'
-            // TODO: have a utility system for getting the viewport pixels for a certain percent of width or height
-            DialogAlign::Left => (32, 256),
-            DialogAlign::Right => (256, 32),
-            DialogAlign::Center => (256, 256),
-            DialogAlign::FullWide => (32, 32),
+            DialogAlign::Left => (edge, side),
+            DialogAlign::Right => (side, edge),
+            DialogAlign::Center => (side, side),
+            DialogAlign::FullWide => (edge, edge),
         }
     }
 }
@@ -63,6 +64,38 @@ pub struct DialogSettings {
     #[export]
     pub dialog_align: DialogAlign,
 
+    /// The wide horizontal margin used by `dialog_align`, as a fraction of the viewport width
+    #[export(range=(0.0, 0.5))]
+    pub dialog_side_margin: f32,
+
+    /// The narrow horizontal margin on the side the dialog is aligned to, as a fraction of the viewport width
+    #[export(range=(0.0, 0.5))]
+    pub dialog_edge_margin: f32,
+
+    /// Space below the dialog box, as a fraction of the viewport height
+    #[export(range=(0.0, 0.5))]
+    pub dialog_bottom_margin: f32,
+
+    /// Minimum height of the dialog box, as a fraction of the viewport height
+    #[export(range=(0.0, 1.0))]
+    pub dialog_min_height: f32,
+
+    /// Padding inside the dialog box, as a fraction of the viewport height
+    #[export(range=(0.0, 0.25))]
+    pub dialog_padding: f32,
+
+    /// Lower clamp for all margins and padding, in pixels
+    #[export]
+    pub dialog_margin_min_pixels: i32,
+
+    /// Upper clamp for all margins and padding, in pixels
+    #[export]
+    pub dialog_margin_max_pixels: i32,
+
+    /// Keeps the dialog box out of display cutouts and rounded corners
+    #[export]
+    pub respect_safe_area: bool,
+
     #[export]
     pub interact_action: GString,
 
@@ -87,12 +120,59 @@ pub struct DialogSettings {
     #[export]
     pub auto_focus_choice_buttons: bool,
 
+    /// input context pushed while dialog is shown, see [InputContextStack](crate::scene::input::input_context::InputContextStack). Taken from the Sqore config if it has one with this name, otherwise an exclusive context with the dialog and `ui_` actions is used. Empty to not push any
+    #[export]
+    pub input_context: StringName,
+
+    /// makes the mouse visible while dialog is shown, through the [MouseCaptureStack](crate::scene::input::mouse_capture::MouseCaptureStack), so choices can be clicked in games that capture the mouse
+    #[export]
+    pub show_mouse: bool,
+
     #[export]
     pub choice_buttons_align: DialogAlign,
 
+    /// moves focus to the previous choice, wrapping around to the last
+    #[export]
+    pub choice_previous_action: GString,
+
+    /// moves focus to the next choice, wrapping around to the first
+    #[export]
+    pub choice_next_action: GString,
+
+    /// selects the focused choice
+    #[export]
+    pub choice_accept_action: GString,
+
+    /// allows selecting choices 1-9 with the number keys
+    #[export]
+    pub choice_number_keys: bool,
+
+    #[export]
+    pub choice_focus_on_hover: bool,
+
+    #[export]
+    pub choice_hover_sfx: Option<Gd<AudioStream>>,
+
+    #[export]
+    pub choice_select_sfx: Option<Gd<AudioStream>>,
+
     #[export]
     pub words_per_minute: f32,
 
+    /// offset from the speaker's origin where bark bubbles are shown
+    #[export]
+    pub bark_offset: Vector3,
+
+    #[export]
+    pub bark_font_size: u32,
+
+    /// reading speed used to decide how long each bark line stays visible
+    #[export]
+    pub bark_words_per_minute: f32,
+
+    #[export]
+    pub bark_min_duration: f32,
+
     base: Base<Resource>,
 }
 
@@ -104,6 +184,14 @@ impl IResource for DialogSettings {
             dialog_font_size: 22u32,
             character_name_label_style: None,
             dialog_align: DialogAlign::Center,
+            dialog_side_margin: 0.15f32,
+            dialog_edge_margin: 0.02f32,
+            dialog_bottom_margin: 0.03f32,
+            dialog_min_height: 0.22f32,
+            dialog_padding: 0.03f32,
+            dialog_margin_min_pixels: 8,
+            dialog_margin_max_pixels: 640,
+            respect_safe_area: true,
             interact_action: "interact".to_godot(),
             anim_appear_duration: 1f32,
             anim_hide_duration: 1f32,
@@ -112,8 +200,21 @@ impl IResource for DialogSettings {
             anim_hide_ease: Default::default(),
             anim_hide_trans: Default::default(),
             auto_focus_choice_buttons: true,
+            input_context: "dialog".into(),
+            show_mouse: true,
             choice_buttons_align: DialogAlign::Center,
+            choice_previous_action: "ui_up".to_godot(),
+            choice_next_action: "ui_down".to_godot(),
+            choice_accept_action: "ui_accept".to_godot(),
+            choice_number_keys: true,
+            choice_focus_on_hover: true,
+            choice_hover_sfx: None,
+            choice_select_sfx: None,
             words_per_minute: 150f32,
+            bark_offset: Vector3::UP * 2f32,
+            bark_font_size: 48u32,
+            bark_words_per_minute: 180f32,
+            bark_min_duration: 1.5f32,
         }
     }
 }
@@ -2,10 +2,13 @@ use self::core_dialog::SqoreDialog;
 use godot::{engine::Engine, prelude::*};
 
 pub mod core_dialog;
+pub mod dialog_bark;
 pub mod dialog_blackboard;
 pub mod dialog_builder;
 pub mod dialog_events;
 pub mod dialog_gui;
+pub mod dialog_markup;
+pub mod dialog_session;
 pub mod dialog_settings;
 pub mod dialog_track;
 
@@ -7,12 +7,18 @@ use godot::{
 use crate::{
     scene::camera::{CameraBrain3D, CAMERA_BRAIN_GROUP},
     scene::game_settings::SqoreConfig,
-    scene::serialization::SqoreSerialized,
+    scene::serialization::{SaveDataBuilder, SqoreSerialized},
 };
 
 const PROJECT_SETTINGS_NAMESPACE: &str = "addons/sqore/";
 const S_LOADERS: &str = "loaders";
 const S_GAME_SETTINGS: &str = "game_settings";
+const PARTICIPANTS_SAVE_PATH: &str = "user://core/participants.json";
+const METHOD_SERIALIZE: &str = "serialize";
+const METHOD_DESERIALIZE: &str = "deserialize";
+/// Keys the settings of the config that are saved as participants are registered under
+const PARTICIPANT_GAMEPLAY: &str = "gameplay";
+const PARTICIPANT_VFX_STACK: &str = "vfx_stack";
 
 pub const SINGLETON_CORE_GLOBALS: &str = "Sqore";
 
@@ -24,6 +30,7 @@ pub fn register_singleton() {
 }
 
 pub fn unregister_singleton() {
+    Sqore::singleton().bind_mut().free_participant_data();
     Engine::singleton().unregister_singleton(StringName::from(SINGLETON_CORE_GLOBALS));
 }
 
@@ -38,6 +45,10 @@ fn get_setting_name(name: &str) -> GString {
 pub struct Sqore {
     #[var]
     config: Gd<SqoreConfig>,
+    /// Objects saved and loaded along with the globals, in registration order
+    participants: Vec<(GString, Gd<Object>)>,
+    /// The last loaded participant data, so participants registered later still receive their data
+    participant_data: Option<Gd<SaveDataBuilder>>,
 
     base: Base<Object>,
 }
@@ -72,8 +83,11 @@ impl IObject for Sqore {
         }
         let mut zelf = Self {
             config: possible_config.unwrap_or(SqoreConfig::new_gd()),
+            participants: Vec::new(),
+            participant_data: None,
             base,
         };
+        zelf.register_config_participants();
         if !Engine::singleton().is_editor_hint() {
             godot_print!("CoreGlobals: loading data from disk");
             zelf.reload_globals();
@@ -111,14 +125,54 @@ impl Sqore {
         }
     }
     #[func]
-    fn save_globals(&mut self) {
-        self.serialize();
+    pub fn save_globals(&self) {
+        self.config.clone().bind_mut().serialize();
+        self.serialize_participants();
     }
 
     #[func]
-    fn reload_globals(&mut self) {
+    pub fn reload_globals(&mut self) {
         self.deserialize();
     }
+
+    /// Registers any object (such as a Resource or Node, from Rust or GDScript) to be saved with `save_globals` and loaded with `reload_globals`. The object needs `serialize(builder: SaveDataBuilder)` and `deserialize(builder: SaveDataBuilder)` methods.
+    ///
+    /// If data for the key was already loaded, `deserialize` is called (deferred) right away. Registering an existing key replaces the previous participant.
+    #[func]
+    fn register_serializable(&mut self, key: GString, participant: Gd<Object>) -> bool {
+        if !participant.has_method(StringName::from(METHOD_SERIALIZE))
+            || !participant.has_method(StringName::from(METHOD_DESERIALIZE))
+        {
+            godot_warn!(
+                "Cannot register \"{}\" as serializable, it needs both `{}` and `{}` methods",
+                key,
+                METHOD_SERIALIZE,
+                METHOD_DESERIALIZE
+            );
+            return false;
+        }
+        self.participants.retain(|(existing, _)| *existing != key);
+        self.participants.push((key.clone(), participant.clone()));
+        if let Some(data) = &mut self.participant_data {
+            if data.bind().has_child_builder(key.clone()) {
+                let child = data.bind_mut().get_child_builder(key);
+                participant
+                    .clone()
+                    .call_deferred(StringName::from(METHOD_DESERIALIZE), &[child.to_variant()]);
+            }
+        }
+        true
+    }
+
+    #[func]
+    fn unregister_serializable(&mut self, key: GString) {
+        self.participants.retain(|(existing, _)| *existing != key);
+    }
+
+    #[func]
+    fn get_serializable_keys(&self) -> Array<GString> {
+        Array::from_iter(self.participants.iter().map(|(key, _)| key.clone()))
+    }
     /// Finds the first [CameraBrain3D] in the tree, if one exists
     #[func]
     fn get_camera_brain(&mut self, tree: Option<Gd<SceneTree>>) -> Option<Gd<CameraBrain3D>> {
@@ -168,13 +222,78 @@ impl Sqore {
     }
 }
 
+impl Sqore {
+    /// Settings of the config that don't need a file of their own are saved as participants
+    fn register_config_participants(&mut self) {
+        let (gameplay, vfx_stack) = {
+            let config = self.config.bind();
+            (config.gameplay.clone(), config.vfx_stack.clone())
+        };
+        if let Some(gameplay) = gameplay {
+            self.register_serializable(PARTICIPANT_GAMEPLAY.to_godot(), gameplay.upcast());
+        }
+        if let Some(vfx_stack) = vfx_stack {
+            self.register_serializable(PARTICIPANT_VFX_STACK.to_godot(), vfx_stack.upcast());
+        }
+    }
+
+    fn serialize_participants(&self) {
+        if self.participants.is_empty() {
+            return;
+        }
+        let mut builder = SaveDataBuilder::new_alloc();
+        for (key, participant) in self.participants.iter() {
+            if !participant.is_instance_valid() {
+                continue;
+            }
+            let child = builder.bind_mut().get_child_builder(key.clone());
+            participant
+                .clone()
+                .call(StringName::from(METHOD_SERIALIZE), &[child.to_variant()]);
+        }
+        builder.bind_mut().save(PARTICIPANTS_SAVE_PATH.to_godot());
+        SaveDataBuilder::free_with_children(builder);
+    }
+
+    fn deserialize_participants(&mut self) {
+        let Some(mut builder) = SaveDataBuilder::try_load_file(PARTICIPANTS_SAVE_PATH.to_godot())
+        else {
+            return;
+        };
+        self.participants
+            .retain(|(_, participant)| participant.is_instance_valid());
+        for (key, participant) in self.participants.iter() {
+            if !builder.bind().has_child_builder(key.clone()) {
+                continue;
+            }
+            let child = builder.bind_mut().get_child_builder(key.clone());
+            // deferred so participants can access Sqore while deserializing
+            participant
+                .clone()
+                .call_deferred(StringName::from(METHOD_DESERIALIZE), &[child.to_variant()]);
+        }
+        // the children stay alive, since deferred calls may still be waiting to receive them
+        if let Some(previous) = self.participant_data.replace(builder) {
+            SaveDataBuilder::free_with_children(previous);
+        }
+    }
+
+    fn free_participant_data(&mut self) {
+        if let Some(data) = self.participant_data.take() {
+            SaveDataBuilder::free_with_children(data);
+        }
+    }
+}
+
 impl SqoreSerialized for Sqore {
     fn serialize(&mut self) {
         // I'm comfy using unwrap because this struct should never be constructed outside of the init function, which assigns the
         self.config.bind_mut().serialize();
+        self.serialize_participants();
     }
 
     fn deserialize(&mut self) {
         self.config.bind_mut().deserialize();
+        self.deserialize_participants();
     }
 }
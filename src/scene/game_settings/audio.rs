@@ -1,4 +1,4 @@
-use crate::scene::serialization::{SaveDataBuilder, SqoreSerialized};
+use crate::scene::serialization::{SettingsFile, SqoreSerialized};
 use godot::prelude::*;
 
 #[derive(GodotClass)]
@@ -26,34 +26,34 @@ impl IResource for GameAudioSettings {
 #[godot_api]
 impl GameAudioSettings {}
 
-const AUDIO_SETTINGS_PATH: &str = "user://core/audio.json";
+impl SettingsFile for GameAudioSettings {
+    const SAVE_PATH: &'static str = "user://core/audio.json";
+    const SCHEMA: &'static str = "audio";
+    const SCHEMA_VERSION: i64 = 1;
+}
+
 impl SqoreSerialized for GameAudioSettings {
     fn serialize(&mut self) {
-        let mut sb = SaveDataBuilder::new_alloc();
-        let mut sbind = sb.bind_mut();
-        sbind.set_value(
-            "audio_db_limit".to_godot(),
-            self.audio_db_limit.to_variant(),
-        );
-        sbind.set_value(
-            "audio_bus_volumes".to_godot(),
-            self.audio_bus_volumes.to_variant(),
-        );
-
-        sbind.save(AUDIO_SETTINGS_PATH.to_godot());
+        Self::write_settings_file(|sbind| {
+            sbind.set_value(
+                "audio_db_limit".to_godot(),
+                self.audio_db_limit.to_variant(),
+            );
+            sbind.set_value(
+                "audio_bus_volumes".to_godot(),
+                self.audio_bus_volumes.to_variant(),
+            );
+        });
     }
 
     fn deserialize(&mut self) {
-        let sb = SaveDataBuilder::try_load_file(AUDIO_SETTINGS_PATH.to_godot());
-        let Some(mut sbgd) = sb else {
-            return;
-        };
-        let mut sbind = sbgd.bind_mut();
-        self.audio_db_limit =
-            sbind.internal_get_value("audio_db_limit".to_godot(), self.audio_db_limit);
-        self.audio_bus_volumes = sbind.internal_get_value(
-            "audio_bus_volumes".to_godot(),
-            self.audio_bus_volumes.clone(),
-        );
+        Self::read_settings_file(|sbind| {
+            self.audio_db_limit =
+                sbind.internal_get_value("audio_db_limit".to_godot(), self.audio_db_limit);
+            self.audio_bus_volumes = sbind.internal_get_value(
+                "audio_bus_volumes".to_godot(),
+                self.audio_bus_volumes.clone(),
+            );
+        });
     }
 }
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use godot::prelude::*;
+
+use crate::scene::serialization::{SettingsFile, SqoreSerialized};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisPreference {
+    pub sensitivity: f32,
+    pub invert_x: bool,
+    pub invert_y: bool,
+}
+
+impl Default for AxisPreference {
+    fn default() -> Self {
+        Self {
+            sensitivity: 1.0,
+            invert_x: false,
+            invert_y: false,
+        }
+    }
+}
+
+/// Player preferences for camera and movement axes, such as look sensitivity and inverted Y.
+///
+/// Preferences are stored under a key that [InputAxisAllocatorResource](crate::scene::input::input_axis_allocator::InputAxisAllocatorResource) sources refer to with their `settings_key`, so mouse look and stick look can be tuned separately.
+#[derive(GodotClass)]
+#[class(tool, base=Resource)]
+pub struct GameAxisSettings {
+    /// Lowest sensitivity that can be set
+    #[export]
+    min_sensitivity: f32,
+    /// Highest sensitivity that can be set
+    #[export]
+    max_sensitivity: f32,
+
+    preferences: HashMap<GString, AxisPreference>,
+    base: Base<Resource>,
+}
+
+#[godot_api]
+impl IResource for GameAxisSettings {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            min_sensitivity: 0.1,
+            max_sensitivity: 5.0,
+            preferences: HashMap::new(),
+            base,
+        }
+    }
+}
+
+#[godot_api]
+impl GameAxisSettings {
+    pub const SIGNAL_AXIS_SETTINGS_CHANGED: &'static str = "axis_settings_changed";
+
+    #[signal]
+    fn axis_settings_changed(key: GString) {}
+
+    #[func]
+    pub fn get_sensitivity(&self, key: GString) -> f32 {
+        self.get_preference(&key).sensitivity
+    }
+
+    #[func]
+    pub fn set_sensitivity(&mut self, key: GString, sensitivity: f32) {
+        let sensitivity = sensitivity.clamp(self.min_sensitivity, self.max_sensitivity);
+        self.update_preference(key, |preference| preference.sensitivity = sensitivity);
+    }
+
+    #[func]
+    pub fn get_invert_x(&self, key: GString) -> bool {
+        self.get_preference(&key).invert_x
+    }
+
+    #[func]
+    pub fn set_invert_x(&mut self, key: GString, invert: bool) {
+        self.update_preference(key, |preference| preference.invert_x = invert);
+    }
+
+    #[func]
+    pub fn get_invert_y(&self, key: GString) -> bool {
+        self.get_preference(&key).invert_y
+    }
+
+    #[func]
+    pub fn set_invert_y(&mut self, key: GString, invert: bool) {
+        self.update_preference(key, |preference| preference.invert_y = invert);
+    }
+
+    /// Restores the default sensitivity and inversion for the key
+    #[func]
+    pub fn reset(&mut self, key: GString) {
+        if self.preferences.remove(&key).is_some() {
+            self.emit_changed(key);
+        }
+    }
+
+    pub fn get_preference(&self, key: &GString) -> AxisPreference {
+        self.preferences.get(key).copied().unwrap_or_default()
+    }
+
+    fn update_preference(&mut self, key: GString, update: impl FnOnce(&mut AxisPreference)) {
+        let preference = self.preferences.entry(key.clone()).or_default();
+        let previous = *preference;
+        update(preference);
+        if previous != *preference {
+            self.emit_changed(key);
+        }
+    }
+
+    fn emit_changed(&mut self, key: GString) {
+        // deferred, since allocators read the settings back when notified
+        self.base_mut().call_deferred(
+            StringName::from("emit_signal"),
+            &[
+                StringName::from(Self::SIGNAL_AXIS_SETTINGS_CHANGED).to_variant(),
+                key.to_variant(),
+            ],
+        );
+    }
+}
+
+impl SettingsFile for GameAxisSettings {
+    const SAVE_PATH: &'static str = "user://core/axes.json";
+    const SCHEMA: &'static str = "axes";
+    const SCHEMA_VERSION: i64 = 1;
+}
+
+impl SqoreSerialized for GameAxisSettings {
+    fn serialize(&mut self) {
+        Self::write_settings_file(|sbind| {
+            for (key, preference) in self.preferences.iter() {
+                let mut dict = Dictionary::new();
+                dict.set("sensitivity", preference.sensitivity);
+                dict.set("invert_x", preference.invert_x);
+                dict.set("invert_y", preference.invert_y);
+                sbind.set_value(key.clone(), dict.to_variant());
+            }
+        });
+    }
+
+    fn deserialize(&mut self) {
+        let mut data = Dictionary::new();
+        if !Self::read_settings_file(|sbind| data = sbind.get_as_dict()) {
+            return;
+        }
+        self.preferences.clear();
+        for (key, value) in data.iter_shared() {
+            let Ok(dict) = Dictionary::try_from_variant(&value) else {
+                continue;
+            };
+            let defaults = AxisPreference::default();
+            let get_or = |name: &str, default: Variant| dict.get(name).unwrap_or(default);
+            let preference = AxisPreference {
+                sensitivity: f32::try_from_variant(&get_or(
+                    "sensitivity",
+                    defaults.sensitivity.to_variant(),
+                ))
+                .unwrap_or(defaults.sensitivity)
+                .clamp(self.min_sensitivity, self.max_sensitivity),
+                invert_x: bool::try_from_variant(&get_or("invert_x", false.to_variant()))
+                    .unwrap_or(false),
+                invert_y: bool::try_from_variant(&get_or("invert_y", false.to_variant()))
+                    .unwrap_or(false),
+            };
+            self.preferences
+                .insert(GString::from_variant(&key), preference);
+        }
+    }
+}
@@ -3,13 +3,20 @@ use std::collections::HashMap;
 use godot::{
     engine::{
         global::{JoyAxis, JoyButton, Key, MouseButton},
-        ClassDb, InputEvent, InputEventJoypadButton, InputEventJoypadMotion, InputEventKey,
-        InputEventMouseButton, InputMap, Json,
+        InputEvent, InputEventAction, InputEventJoypadButton, InputEventJoypadMotion,
+        InputEventKey, InputEventMouseButton, InputMap,
     },
     prelude::*,
 };
 
-use crate::scene::serialization::{SaveDataBuilder, SqoreSerialized};
+use super::saved_binds::{Field, SavedBind};
+use crate::{
+    scene::{
+        input::rebind_listener::{RebindListener, DEVICE_ALL},
+        serialization::{SettingsFile, SqoreSerialized},
+    },
+    util::SqoreUtil,
+};
 
 #[derive(Var, PartialEq, PartialOrd, Eq, Ord, GodotConvert)]
 #[godot(via=i32)]
@@ -29,46 +36,6 @@ impl MappingsStyle {
     }
 }
 
-#[derive(Var, PartialEq, PartialOrd, Eq, Ord, GodotConvert)]
-#[godot(via = i32)]
-enum HandledInputEvents {
-    JoypadButton = 0,
-    JoypadMotion = 1,
-    Key = 2,
-    MouseButton = 3,
-    Generic = 4,
-}
-
-impl HandledInputEvents {
-    fn ord(index: usize) -> Option<Self> {
-        match index {
-            0 => Some(Self::JoypadButton),
-            1 => Some(Self::JoypadMotion),
-            2 => Some(Self::Key),
-            3 => Some(Self::MouseButton),
-            4 => Some(Self::Generic),
-            _ => None,
-        }
-    }
-}
-
-// impl ToPrimitive for HandledInputEvents {
-//     fn to_i64(&self) -> Option<i64> {
-//         match self {
-//             HandledInputEvents::JoypadButton => Some(0),
-//             HandledInputEvents::JoypadMotion => Some(1),
-//             HandledInputEvents::Key => Some(2),
-//             HandledInputEvents::MouseButton => Some(3),
-//             HandledInputEvents::Generic => Some(4),
-//         }
-//     }
-
-//     fn to_u64(&self) -> Option<u64> {
-//         let val = self.to_i64();
-//         Some(u64::try_from(val.unwrap_or(0)).unwrap_or(0))
-//     }
-// }
-
 type MappingStorage = HashMap<GString, Array<Gd<InputEvent>>>;
 #[derive(GodotClass)]
 #[class(tool, base=Resource)]
@@ -82,6 +49,7 @@ pub struct GameControlsSettings {
     // internal
     base_mappings: MappingStorage,
     mapping_overrides: MappingStorage,
+    rebind_listener: Option<Gd<RebindListener>>,
     base: Base<Resource>,
 }
 #[godot_api]
@@ -101,6 +69,7 @@ impl IResource for GameControlsSettings {
             allowed_mappings: PackedStringArray::new(),
             base_mappings,
             mapping_overrides: MappingStorage::new(),
+            rebind_listener: None,
             base,
         }
     }
@@ -108,270 +77,428 @@ impl IResource for GameControlsSettings {
 
 #[godot_api]
 impl GameControlsSettings {
+    pub const SIGNAL_REBIND_STARTED: &'static str = "rebind_started";
+    pub const SIGNAL_REBIND_FINISHED: &'static str = "rebind_finished";
+    pub const SIGNAL_REBIND_CANCELLED: &'static str = "rebind_cancelled";
+    pub const SIGNAL_BIND_CONFLICT: &'static str = "bind_conflict";
+    pub const SIGNAL_BINDS_CHANGED: &'static str = "binds_changed";
+
+    #[signal]
+    fn rebind_started(action: GString, slot: i32) {}
+
+    #[signal]
+    fn rebind_finished(action: GString, slot: i32, event: Gd<InputEvent>) {}
+
+    #[signal]
+    fn rebind_cancelled(action: GString, slot: i32) {}
+
+    /// Emitted when an event is bound that other actions already use. The bind is kept either way, resolving the conflict (such as with `clear_bind` on the other action) is up to the game
+    #[signal]
+    fn bind_conflict(action: GString, event: Gd<InputEvent>, conflicting_actions: Array<GString>) {}
+
+    #[signal]
+    fn binds_changed(action: GString) {}
+
     #[func]
     fn load_binds(&self) {
-        for (action, mappings) in self.mapping_overrides.clone() {
-            let sn = StringName::from(action.to_string());
-            InputMap::singleton().action_erase_events(sn.clone());
-            for map in mappings.iter_shared() {
-                InputMap::singleton().action_add_event(sn.clone(), map)
-            }
+        for action in self.mapping_overrides.keys() {
+            self.apply_action(action);
         }
     }
+
+    /// All actions the player may rebind, sorted by name
     #[func]
-    fn can_bind(&self, action: GString) -> bool {
-        let option_map_style: Option<MappingsStyle> =
-            MappingsStyle::ord(self.mappings_style as usize);
-        let Some(map_style) = option_map_style else {
-            godot_warn!("failed to map internal rust->MappingsStyle to i32. This will break deserialization for controls bindings");
-            return false;
-        };
-        match map_style {
-            MappingsStyle::All => true,
-            MappingsStyle::AllCustom => !action.to_string().starts_with("input_"),
-            MappingsStyle::OnlySpecified => self.allowed_mappings.contains(action),
+    pub fn get_bindable_actions(&self) -> Array<GString> {
+        let mut actions: Vec<GString> = self
+            .base_mappings
+            .keys()
+            .filter(|action| self.can_bind((*action).clone()))
+            .cloned()
+            .collect();
+        actions.sort_by_key(|action| action.to_string());
+        Array::from_iter(actions)
+    }
+
+    /// The events currently bound to the action, in slot order
+    #[func]
+    pub fn get_binds(&self, action: GString) -> Array<Gd<InputEvent>> {
+        self.mapping_overrides
+            .get(&action)
+            .or_else(|| self.base_mappings.get(&action))
+            .map(|binds| binds.duplicate_shallow())
+            .unwrap_or_default()
+    }
+
+    #[func]
+    pub fn get_bind(&self, action: GString, slot: i32) -> Option<Gd<InputEvent>> {
+        let binds = self.get_binds(action);
+        if slot < 0 || slot as usize >= binds.len() {
+            return None;
         }
+        Some(binds.get(slot as usize))
     }
+
+    /// Whether the action differs from its default binds
     #[func]
-    fn push_bind(&mut self, action_name: GString, event: Gd<InputEvent>) {
-        if let Some(arr) = self.mapping_overrides.get_mut(&action_name) {
-            arr.push(event.upcast());
+    pub fn is_action_customized(&self, action: GString) -> bool {
+        self.mapping_overrides.contains_key(&action)
+    }
+
+    /// Replaces the event in the slot. Slots past the end (or negative slots) add a new bind instead
+    #[func]
+    pub fn set_bind(&mut self, action: GString, slot: i32, event: Gd<InputEvent>) -> bool {
+        if !self.can_bind(action.clone()) {
+            godot_warn!("Action \"{}\" can't be rebound", action);
+            return false;
+        }
+        let conflicts = self.find_conflicts(event.clone(), action.clone());
+        let mut binds = self.get_binds(action.clone());
+        let index = if slot >= 0 && (slot as usize) < binds.len() {
+            binds.set(slot as usize, event.clone());
+            slot as usize
         } else {
-            let mut arr: Array<Gd<InputEvent>> = Array::new();
-            arr.push(event);
-            self.mapping_overrides.insert(action_name, arr);
+            binds.push(event.clone());
+            binds.len() - 1
+        };
+        // the same event in another slot would only be a duplicate
+        let mut duplicates: Vec<usize> = binds
+            .iter_shared()
+            .enumerate()
+            .filter(|(i, bind)| *i != index && bind.is_match(event.clone()))
+            .map(|(i, _)| i)
+            .collect();
+        duplicates.reverse();
+        for i in duplicates {
+            binds.remove(i);
+        }
+        self.mapping_overrides.insert(action.clone(), binds);
+        self.apply_action(&action);
+        if !conflicts.is_empty() {
+            self.emit_deferred(
+                Self::SIGNAL_BIND_CONFLICT,
+                &[
+                    action.to_variant(),
+                    event.to_variant(),
+                    conflicts.to_variant(),
+                ],
+            );
         }
+        self.emit_deferred(Self::SIGNAL_BINDS_CHANGED, &[action.to_variant()]);
+        true
     }
 
-    fn bind_generic(&mut self, data_dict: Dictionary, action_name: GString) {
-        godot_print!("Trying to load generic event for action\"{}\"", action_name);
-        // this is where I would usually use some kind of reflection? Not sure how I could manage that?
-        let res_str_data = GString::try_from_variant(&data_dict.get_or_nil("json_data"));
-        let Ok(str_data) = res_str_data else {
-            return;
-        };
-        if !str_data.to_string().starts_with("\"InputEvent") {
-            // guard against non-inputevent types (which all have InputEvent* as a prefix)
+    /// Removes the event in the slot. Later slots move up by one
+    #[func]
+    pub fn clear_bind(&mut self, action: GString, slot: i32) {
+        let mut binds = self.get_binds(action.clone());
+        if slot < 0 || slot as usize >= binds.len() {
             return;
         }
-        let stringval = str_data.to_string();
-        let parts: Vec<&str> = stringval.splitn(3, '\"').collect();
-        if parts.len() != 3 {
+        binds.remove(slot as usize);
+        self.mapping_overrides.insert(action.clone(), binds);
+        self.apply_action(&action);
+        self.emit_deferred(Self::SIGNAL_BINDS_CHANGED, &[action.to_variant()]);
+    }
+
+    /// Bindable actions (other than `ignored_action`) that already have a bind matching the event
+    #[func]
+    pub fn find_conflicts(&self, event: Gd<InputEvent>, ignored_action: GString) -> Array<GString> {
+        Array::from_iter(
+            self.get_bindable_actions()
+                .iter_shared()
+                .filter(|action| *action != ignored_action)
+                .filter(|action| {
+                    Self::find_matching(&self.get_binds(action.clone()), &event).is_some()
+                }),
+        )
+    }
+
+    /// Restores the default binds of the action
+    #[func]
+    pub fn reset_action(&mut self, action: GString) {
+        if self.mapping_overrides.remove(&action).is_none() {
             return;
         }
-        godot_print!(
-            "Attempting to initialize an InputEvent of type \"{}\"",
-            parts[1]
-        );
-        let sname = StringName::from(parts[1]);
-        let classes = ClassDb::singleton();
-        if classes.class_exists(sname.clone()) && classes.can_instantiate(sname.clone()) {
-            #[allow(unused)] // just to clear this pesky warning
-            let instance = classes.instantiate(sname);
-            // how to we cast this variant to an object type? (Which would allow us to try to downcast)
+        self.apply_action(&action);
+        self.emit_deferred(Self::SIGNAL_BINDS_CHANGED, &[action.to_variant()]);
+    }
+
+    #[func]
+    pub fn reset_all(&mut self) {
+        let actions: Vec<GString> = self.mapping_overrides.keys().cloned().collect();
+        for action in actions {
+            self.reset_action(action);
         }
     }
 
-    fn bind_joypad_button(&mut self, data_dict: Dictionary, action_name: GString) {
-        let mut event = InputEventJoypadButton::new_gd();
-        let Some(index) = SaveDataBuilder::get_entry_from(&data_dict, "button_index") else {
-            return;
-        };
-        let Some(pressed) = SaveDataBuilder::get_entry_from(&data_dict, "pressed") else {
-            return;
+    /// Captures the next key, mouse button, or joypad input and binds it to the slot (see `set_bind`). Emits `rebind_finished`, or `rebind_cancelled` if the player presses escape or `cancel_rebind` is called
+    #[func]
+    pub fn start_rebind(&mut self, action: GString, slot: i32) -> bool {
+        if !self.can_bind(action.clone()) {
+            godot_warn!("Action \"{}\" can't be rebound", action);
+            return false;
+        }
+        self.cancel_rebind();
+        let Some(tree) = SqoreUtil::get_scene_tree_global() else {
+            godot_warn!("Failed to find SceneTree when starting a rebind");
+            return false;
         };
-        let Some(pressure) = SaveDataBuilder::get_entry_from(&data_dict, "pressure") else {
-            return;
+        let Some(root) = &mut tree.get_root() else {
+            return false;
         };
-        event.set_button_index(JoyButton::from_ord(index));
-        event.set_pressed(pressed);
-        event.set_pressure(pressure);
-        self.push_bind(action_name, event.upcast());
+        let mut listener = RebindListener::new_alloc();
+        {
+            let mut bind = listener.bind_mut();
+            bind.settings = Some(self.to_gd());
+            bind.action = action.clone();
+            bind.slot = slot;
+        }
+        self.rebind_listener = Some(listener.clone());
+        root.call_deferred(StringName::from("add_child"), &[listener.to_variant()]);
+        self.emit_deferred(
+            Self::SIGNAL_REBIND_STARTED,
+            &[action.to_variant(), slot.to_variant()],
+        );
+        true
     }
-    fn bind_joypad_motion(&mut self, data_dict: Dictionary, action_name: GString) {
-        let mut event = InputEventJoypadMotion::new_gd();
-        let Some(axis) = SaveDataBuilder::get_entry_from(&data_dict, "axis") else {
+
+    #[func]
+    pub fn cancel_rebind(&mut self) {
+        let Some(listener) = self.rebind_listener.take() else {
             return;
         };
-        let Some(axis_value) = SaveDataBuilder::get_entry_from(&data_dict, "axis_value") else {
+        if !listener.is_instance_valid() {
             return;
+        }
+        let (action, slot) = {
+            let bind = listener.bind();
+            (bind.action.clone(), bind.slot)
         };
-        event.set_axis(JoyAxis::from_ord(axis));
-        event.set_axis_value(axis_value);
-        self.push_bind(action_name, event.upcast());
+        listener.clone().queue_free();
+        self.emit_deferred(
+            Self::SIGNAL_REBIND_CANCELLED,
+            &[action.to_variant(), slot.to_variant()],
+        );
     }
-    fn bind_key(&mut self, data_dict: Dictionary, action_name: GString) {
-        let mut event = InputEventKey::new_gd();
-        let Some(echo) = SaveDataBuilder::get_entry_from(&data_dict, "key") else {
-            return;
-        };
-        let Some(key_label) = SaveDataBuilder::get_entry_from(&data_dict, "key") else {
-            return;
-        };
-        let Some(keycode) = SaveDataBuilder::get_entry_from(&data_dict, "key") else {
-            return;
-        };
-        let Some(physical_keycode) = SaveDataBuilder::get_entry_from(&data_dict, "key") else {
-            return;
-        };
-        let Some(pressed) = SaveDataBuilder::get_entry_from(&data_dict, "key") else {
-            return;
-        };
-        let Some(unicode) = SaveDataBuilder::get_entry_from(&data_dict, "key") else {
-            return;
-        };
-        event.set_echo(echo);
-        event.set_key_label(key_label);
-        event.set_keycode(Key::from_ord(keycode));
-        event.set_physical_keycode(Key::from_ord(physical_keycode));
-        event.set_pressed(pressed);
-        event.set_unicode(unicode);
-        self.push_bind(action_name, event.upcast());
+
+    #[func]
+    pub fn is_rebinding(&self) -> bool {
+        self.rebind_listener
+            .as_ref()
+            .is_some_and(|listener| listener.is_instance_valid())
     }
-    fn bind_mouse_button(&mut self, data_dict: Dictionary, action_name: GString) {
-        let mut event = InputEventMouseButton::new_gd();
-        let Some(button_index) = SaveDataBuilder::get_entry_from(&data_dict, "button_index") else {
-            return;
-        };
-        let Some(cancelled) = SaveDataBuilder::get_entry_from(&data_dict, "cancelled") else {
-            return;
-        };
-        let Some(double_click) = SaveDataBuilder::get_entry_from(&data_dict, "double_click") else {
-            return;
-        };
-        let Some(factor) = SaveDataBuilder::get_entry_from(&data_dict, "factor") else {
-            return;
-        };
-        let Some(pressed) = SaveDataBuilder::get_entry_from(&data_dict, "pressed") else {
+
+    /// Called by the [RebindListener] once it captured an event
+    #[func]
+    fn finish_rebind(&mut self, action: GString, slot: i32, event: Gd<InputEvent>) {
+        // the rebind was cancelled while this call was deferred
+        let Some(mut listener) = self.rebind_listener.take() else {
             return;
         };
-        event.set_button_index(MouseButton::from_ord(button_index));
-        event.set_canceled(cancelled);
-        event.set_double_click(double_click);
-        event.set_factor(factor);
-        event.set_pressed(pressed);
-        self.push_bind(action_name, event.upcast());
+        if listener.is_instance_valid() {
+            listener.queue_free();
+        }
+        if self.set_bind(action.clone(), slot, event.clone()) {
+            self.emit_deferred(
+                Self::SIGNAL_REBIND_FINISHED,
+                &[action.to_variant(), slot.to_variant(), event.to_variant()],
+            );
+        }
     }
 
-    // JoypadButton = 0,
-    // JoypadMotion = 1,
-    // Key = 2,
-    // MouseButton = 3,
-
-    fn ser_generic(&self, event: Gd<InputEvent>) -> Option<Dictionary> {
-        let mut dict = Dictionary::new();
-        dict.insert(
-            "type".to_godot(),
-            HandledInputEvents::Generic.get_property(),
-        );
-
-        let data = Json::stringify(event.to_variant());
-        dict.insert("json_data", data);
-        Some(dict)
+    /// Sets the InputMap events of the action to its current binds
+    fn apply_action(&self, action: &GString) {
+        let sn = StringName::from(action);
+        let mut map = InputMap::singleton();
+        if !map.has_action(sn.clone()) {
+            godot_warn!("Found binds for unknown action \"{}\"", action);
+            return;
+        }
+        map.action_erase_events(sn.clone());
+        for event in self.get_binds(action.clone()).iter_shared() {
+            map.action_add_event(sn.clone(), event);
+        }
     }
 
-    fn ser_joy_button(&self, event: Gd<InputEvent>) -> Option<Dictionary> {
-        let rcast: Result<Gd<InputEventJoypadButton>, _> = event.try_cast();
-        let Ok(event) = rcast else {
-            return None;
-        };
-        let mut dict = Dictionary::new();
-        dict.insert(
-            "type".to_godot(),
-            HandledInputEvents::JoypadButton.get_property(),
-        );
-        dict.insert("button_index".to_godot(), event.get_button_index().ord());
-        dict.insert("pressed".to_godot(), event.is_pressed());
-        dict.insert("pressure".to_godot(), event.get_pressure());
-
-        Some(dict)
+    fn find_matching(binds: &Array<Gd<InputEvent>>, event: &Gd<InputEvent>) -> Option<usize> {
+        binds
+            .iter_shared()
+            .position(|bind| bind.is_match(event.clone()))
     }
-    fn ser_joy_motion(&self, event: Gd<InputEvent>) -> Option<Dictionary> {
-        let rcast: Result<Gd<InputEventJoypadMotion>, _> = event.try_cast();
-        let Ok(event) = rcast else {
-            return None;
-        };
-        let mut dict = Dictionary::new();
-        dict.insert(
-            "type".to_godot(),
-            HandledInputEvents::JoypadMotion.get_property(),
-        );
 
-        dict.insert("axis".to_godot(), event.get_axis());
-        dict.insert("axis_value".to_godot(), event.get_axis_value());
-
-        Some(dict)
+    fn emit_deferred(&mut self, signal: &str, args: &[Variant]) {
+        let mut call_args = vec![StringName::from(signal).to_variant()];
+        call_args.extend_from_slice(args);
+        self.base_mut()
+            .call_deferred(StringName::from("emit_signal"), &call_args);
     }
-    fn ser_key(&self, e: Gd<InputEvent>) -> Option<Dictionary> {
-        let rcast: Result<Gd<InputEventKey>, _> = e.try_cast();
-        let Ok(event) = rcast else {
-            return None;
+    #[func]
+    fn can_bind(&self, action: GString) -> bool {
+        let option_map_style: Option<MappingsStyle> =
+            MappingsStyle::ord(self.mappings_style as usize);
+        let Some(map_style) = option_map_style else {
+            godot_warn!("failed to map internal rust->MappingsStyle to i32. This will break deserialization for controls bindings");
+            return false;
         };
-        let mut dict = Dictionary::new();
-        dict.insert("type".to_godot(), HandledInputEvents::Key.get_property());
-        dict.insert("echo".to_godot(), event.is_echo());
-        dict.insert("key_label".to_godot(), event.get_key_label());
-        dict.insert("keycode".to_godot(), event.get_keycode().ord());
-        dict.insert(
-            "physical_keycode".to_godot(),
-            event.get_physical_keycode().ord(),
-        );
-        dict.insert("pressed".to_godot(), event.is_pressed());
-        dict.insert("unicode".to_godot(), event.get_unicode());
+        match map_style {
+            MappingsStyle::All => true,
+            MappingsStyle::AllCustom => !action.to_string().starts_with("input_"),
+            MappingsStyle::OnlySpecified => self.allowed_mappings.contains(action),
+        }
+    }
+    /// Adds the event to the end of the action's overrides, unless it is already bound
+    #[func]
+    fn push_bind(&mut self, action_name: GString, event: Gd<InputEvent>) {
+        let arr = self.mapping_overrides.entry(action_name).or_default();
+        if Self::find_matching(arr, &event).is_none() {
+            arr.push(event);
+        }
+    }
 
-        Some(dict)
+    /// The fields of the event that are stored in the settings file, `None` for events that can't be saved
+    fn event_to_saved(event: &Gd<InputEvent>) -> Option<SavedBind> {
+        if let Ok(button) = event.clone().try_cast::<InputEventJoypadButton>() {
+            return Some(SavedBind::JoypadButton {
+                button_index: button.get_button_index().ord() as i64,
+            });
+        }
+        if let Ok(motion) = event.clone().try_cast::<InputEventJoypadMotion>() {
+            return Some(SavedBind::JoypadMotion {
+                axis: motion.get_axis().ord() as i64,
+                axis_value: motion.get_axis_value() as f64,
+            });
+        }
+        if let Ok(key) = event.clone().try_cast::<InputEventKey>() {
+            return Some(SavedBind::Key {
+                keycode: key.get_keycode().ord() as i64,
+                physical_keycode: key.get_physical_keycode().ord() as i64,
+                key_label: key.get_key_label().ord() as i64,
+                unicode: i64::from(key.get_unicode()),
+            });
+        }
+        if let Ok(button) = event.clone().try_cast::<InputEventMouseButton>() {
+            return Some(SavedBind::MouseButton {
+                button_index: button.get_button_index().ord() as i64,
+            });
+        }
+        if let Ok(action) = event.clone().try_cast::<InputEventAction>() {
+            return Some(SavedBind::Action {
+                action: action.get_action().to_string(),
+                strength: action.get_strength() as f64,
+            });
+        }
+        None
     }
-    fn ser_mouse_button(&self, event: Gd<InputEvent>) -> Option<Dictionary> {
-        let rcast: Result<Gd<InputEventMouseButton>, _> = event.try_cast();
-        let Ok(event) = rcast else {
-            return None;
+
+    /// Rebuilds the event from its stored fields. Like captured binds, it matches every device and has no pressed state
+    fn saved_to_event(saved: SavedBind) -> Option<Gd<InputEvent>> {
+        let mut event: Gd<InputEvent> = match saved {
+            SavedBind::JoypadButton { button_index } => {
+                let mut event = InputEventJoypadButton::new_gd();
+                event.set_button_index(JoyButton::try_from_ord(button_index as i32)?);
+                event.upcast()
+            }
+            SavedBind::JoypadMotion { axis, axis_value } => {
+                let mut event = InputEventJoypadMotion::new_gd();
+                event.set_axis(JoyAxis::try_from_ord(axis as i32)?);
+                event.set_axis_value(axis_value as f32);
+                event.upcast()
+            }
+            SavedBind::Key {
+                keycode,
+                physical_keycode,
+                key_label,
+                unicode,
+            } => {
+                let mut event = InputEventKey::new_gd();
+                event.set_keycode(Key::try_from_ord(keycode as i32)?);
+                event.set_physical_keycode(Key::try_from_ord(physical_keycode as i32)?);
+                event.set_key_label(Key::try_from_ord(key_label as i32)?);
+                event.set_unicode(unicode.try_into().ok()?);
+                event.upcast()
+            }
+            SavedBind::MouseButton { button_index } => {
+                let mut event = InputEventMouseButton::new_gd();
+                event.set_button_index(MouseButton::try_from_ord(button_index as i32)?);
+                event.upcast()
+            }
+            SavedBind::Action { action, strength } => {
+                let mut event = InputEventAction::new_gd();
+                event.set_action(StringName::from(action.as_str()));
+                event.set_strength(strength as f32);
+                event.upcast()
+            }
         };
+        event.set_device(DEVICE_ALL);
+        Some(event)
+    }
+
+    fn saved_to_dict(saved: &SavedBind) -> Dictionary {
         let mut dict = Dictionary::new();
-        dict.insert(
-            "type".to_godot(),
-            HandledInputEvents::MouseButton.get_property(),
-        );
-        dict.insert("button_index".to_godot(), event.get_button_index());
-        dict.insert("cancelled".to_godot(), event.is_canceled());
-        dict.insert("double_click".to_godot(), event.is_double_click());
-        dict.insert("factor".to_godot(), event.get_factor());
-        dict.insert("pressed".to_godot(), event.is_pressed());
+        for (key, field) in saved.to_fields() {
+            let value = match field {
+                Field::Int(value) => value.to_variant(),
+                Field::Float(value) => value.to_variant(),
+                Field::Text(value) => value.to_variant(),
+            };
+            dict.set(key, value);
+        }
+        dict
+    }
 
-        Some(dict)
+    fn dict_to_saved(dict: &Dictionary) -> Result<SavedBind, String> {
+        SavedBind::from_fields(|key| {
+            let value = dict.get(key)?;
+            match value.get_type() {
+                VariantType::Int => Some(Field::Int(i64::from_variant(&value))),
+                VariantType::Float => Some(Field::Float(f64::from_variant(&value))),
+                VariantType::String => Some(Field::Text(GString::from_variant(&value).to_string())),
+                VariantType::StringName => {
+                    Some(Field::Text(StringName::from_variant(&value).to_string()))
+                }
+                _ => None,
+            }
+        })
     }
 }
 
-const CONTROLS_SETTINGS_PATH: &str = "user://core/controls.json";
+impl SettingsFile for GameControlsSettings {
+    const SAVE_PATH: &'static str = "user://core/controls.json";
+    const SCHEMA: &'static str = "controls";
+    const SCHEMA_VERSION: i64 = 1;
+}
+
 impl SqoreSerialized for GameControlsSettings {
     fn serialize(&mut self) {
-        let mut sb = SaveDataBuilder::new_alloc();
-        let mut sbind = sb.bind_mut();
-        for (key, values) in self.mapping_overrides.clone() {
-            let mut data_arr: Array<Dictionary> = Array::new();
-            for event in values.iter_shared() {
-                if let Some(dict) = self.ser_joy_button(event.clone()) {
-                    data_arr.push(dict);
-                } else if let Some(dict) = self.ser_joy_motion(event.clone()) {
-                    data_arr.push(dict);
-                } else if let Some(dict) = self.ser_key(event.clone()) {
-                    data_arr.push(dict);
-                } else if let Some(dict) = self.ser_mouse_button(event.clone()) {
-                    data_arr.push(dict);
-                } else if let Some(dict) = self.ser_generic(event) {
-                    data_arr.push(dict);
+        Self::write_settings_file(|sbind| {
+            for (key, values) in self.mapping_overrides.clone() {
+                let mut data_arr: Array<Dictionary> = Array::new();
+                for event in values.iter_shared() {
+                    match Self::event_to_saved(&event) {
+                        Some(saved) => data_arr.push(Self::saved_to_dict(&saved)),
+                        None => godot_warn!(
+                            "Input event \"{}\" bound to action \"{}\" can't be saved",
+                            event.get_class(),
+                            key
+                        ),
+                    }
                 }
+                sbind.set_value(key, data_arr.to_variant());
             }
-            sbind.set_value(key, data_arr.to_variant());
-        }
-        sbind.save(CONTROLS_SETTINGS_PATH.to_godot());
+        });
     }
 
     fn deserialize(&mut self) {
-        let Some(mut sb) = SaveDataBuilder::try_load_file(CONTROLS_SETTINGS_PATH.to_godot()) else {
+        let mut custom_bindings_dictionary = Dictionary::new();
+        if !Self::read_settings_file(|sbind| custom_bindings_dictionary = sbind.get_as_dict()) {
             return;
-        };
-        let mut sbind = sb.bind_mut();
-        let custom_bindings_dictionary = sbind.get_as_dict();
+        }
+        // start from the defaults, so actions that are no longer overridden get their default binds back
+        let previous: Vec<GString> = self.mapping_overrides.keys().cloned().collect();
+        self.mapping_overrides.clear();
+        for action in previous {
+            self.apply_action(&action);
+        }
         for (key, value) in custom_bindings_dictionary.iter_shared() {
             if !self.can_bind(GString::from_variant(&key)) {
                 godot_warn!("Found disallowed binding on disk! This is probably fine, but check into it if you keep seeing this!");
@@ -379,29 +506,28 @@ impl SqoreSerialized for GameControlsSettings {
             }
             let action_name = GString::from_variant(&key);
 
-            let values: Array<Dictionary> = Array::try_from_variant(&value).unwrap_or_default();
+            // parsed arrays are untyped, so each entry is checked on its own
+            let values = VariantArray::try_from_variant(&value).unwrap_or_default();
             godot_print!("Found mappings on disk for: {} ", key);
+            // an action can be saved without any binds, which should stay that way
+            self.mapping_overrides
+                .entry(action_name.clone())
+                .or_default();
             for map in values.iter_shared() {
-                let mut event_type = HandledInputEvents::Generic;
-                if let Ok(type_id) = i32::try_from_variant(&map.get_or_nil("type".to_godot())) {
-                    let opt_event_type: Option<HandledInputEvents> =
-                        HandledInputEvents::ord(type_id as usize);
-                    if let Some(e_type) = opt_event_type {
-                        event_type = e_type;
-                    }
-                }
-                match event_type {
-                    HandledInputEvents::JoypadButton => {
-                        self.bind_joypad_button(map, action_name.clone())
-                    }
-                    HandledInputEvents::JoypadMotion => {
-                        self.bind_joypad_motion(map, action_name.clone())
-                    }
-                    HandledInputEvents::Key => self.bind_key(map, action_name.clone()),
-                    HandledInputEvents::MouseButton => {
-                        self.bind_mouse_button(map, action_name.clone())
-                    }
-                    HandledInputEvents::Generic => self.bind_generic(map, action_name.clone()),
+                let event = Dictionary::try_from_variant(&map)
+                    .map_err(|_| String::from("not a dictionary"))
+                    .and_then(|map| Self::dict_to_saved(&map))
+                    .and_then(|saved| {
+                        Self::saved_to_event(saved)
+                            .ok_or_else(|| String::from("invalid input index"))
+                    });
+                match event {
+                    Ok(event) => self.push_bind(action_name.clone(), event),
+                    Err(err) => godot_warn!(
+                        "Failed to load saved input event for action \"{}\": {}",
+                        action_name,
+                        err
+                    ),
                 }
             }
         }
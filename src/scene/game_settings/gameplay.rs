@@ -1,5 +1,7 @@
 use godot::prelude::*;
 
+use crate::scene::serialization::SaveDataBuilder;
+
 #[derive(GodotClass)]
 #[class(base=Resource)]
 pub struct GameGameplaySettings {
@@ -61,6 +63,42 @@ impl GameGameplaySettings {
         }
         GString::from("")
     }
+
+    /// Stores every option value in the builder. These settings are saved as a participant of `Sqore`
+    #[func]
+    pub fn serialize(&self, mut builder: Gd<SaveDataBuilder>) {
+        let mut sbind = builder.bind_mut();
+        for option in self.options_bool.iter_shared() {
+            let option = option.bind();
+            sbind.set_value(option.option_key.clone(), option.value.to_variant());
+        }
+        for option in self.options_number.iter_shared() {
+            let option = option.bind();
+            sbind.set_value(option.option_key.clone(), option.value.to_variant());
+        }
+        for option in self.options_string.iter_shared() {
+            let option = option.bind();
+            sbind.set_value(option.option_key.clone(), option.value.to_variant());
+        }
+    }
+
+    #[func]
+    pub fn deserialize(&mut self, mut builder: Gd<SaveDataBuilder>) {
+        let mut sbind = builder.bind_mut();
+        for mut option in self.options_bool.iter_shared() {
+            let mut option = option.bind_mut();
+            option.value = sbind.internal_get_value(option.option_key.clone(), option.value);
+        }
+        for mut option in self.options_number.iter_shared() {
+            let mut option = option.bind_mut();
+            option.value = sbind.internal_get_value(option.option_key.clone(), option.value);
+        }
+        for mut option in self.options_string.iter_shared() {
+            let mut option = option.bind_mut();
+            let value = option.value.clone();
+            option.value = sbind.internal_get_value(option.option_key.clone(), value);
+        }
+    }
 }
 
 //
@@ -3,7 +3,7 @@ use godot::{
     prelude::*,
 };
 
-use crate::scene::serialization::{SaveDataBuilder, SqoreSerialized};
+use crate::scene::serialization::{SettingsFile, SqoreSerialized};
 
 #[derive(GodotClass)]
 #[class(tool, base=Resource)]
@@ -86,75 +86,80 @@ impl GameGraphicsSettings {
     }
 }
 
-const GRAPHICS_SAVE_PATH: &str = "user://core/graphics.json";
+impl SettingsFile for GameGraphicsSettings {
+    const SAVE_PATH: &'static str = "user://core/graphics.json";
+    const SCHEMA: &'static str = "graphics";
+    const SCHEMA_VERSION: i64 = 1;
+}
 
 impl SqoreSerialized for GameGraphicsSettings {
     fn serialize(&mut self) {
-        let mut save = SaveDataBuilder::new_alloc();
-        let mut bind = save.bind_mut();
-        bind.set_value("use_ssao".to_godot(), self.use_ssao.to_variant());
-        bind.set_value("use_bloom".to_godot(), self.use_bloom.to_variant());
-        bind.set_value("use_sdfgi".to_godot(), self.use_sdfgi.to_variant());
-        bind.set_value("use_ssil".to_godot(), self.use_ssil.to_variant());
-        bind.set_value("use_ssr".to_godot(), self.use_ssr.to_variant());
-        bind.set_value(
-            "value_brightness".to_godot(),
-            self.value_brightness.to_variant(),
-        );
-        bind.set_value(
-            "value_contrast".to_godot(),
-            self.value_contrast.to_variant(),
-        );
-        bind.set_value(
-            "value_saturation".to_godot(),
-            self.value_saturation.to_variant(),
-        );
-        bind.set_value(
-            "value_exposure".to_godot(),
-            self.value_exposure.to_variant(),
-        );
-        bind.set_value(
-            "window_fullscreen_mode".to_godot(),
-            self.window_fullscreen_mode.to_variant(),
-        );
-        bind.set_value(
-            "scaling_algorithm".to_godot(),
-            self.scaling_algorithm.to_variant(),
-        );
-        bind.save(GRAPHICS_SAVE_PATH.into_godot());
+        Self::write_settings_file(|bind| {
+            bind.set_value("use_ssao".to_godot(), self.use_ssao.to_variant());
+            bind.set_value("use_bloom".to_godot(), self.use_bloom.to_variant());
+            bind.set_value("use_sdfgi".to_godot(), self.use_sdfgi.to_variant());
+            bind.set_value("use_ssil".to_godot(), self.use_ssil.to_variant());
+            bind.set_value("use_ssr".to_godot(), self.use_ssr.to_variant());
+            bind.set_value(
+                "value_brightness".to_godot(),
+                self.value_brightness.to_variant(),
+            );
+            bind.set_value(
+                "value_contrast".to_godot(),
+                self.value_contrast.to_variant(),
+            );
+            bind.set_value(
+                "value_saturation".to_godot(),
+                self.value_saturation.to_variant(),
+            );
+            bind.set_value(
+                "value_exposure".to_godot(),
+                self.value_exposure.to_variant(),
+            );
+            bind.set_value(
+                "window_fullscreen_mode".to_godot(),
+                self.window_fullscreen_mode.to_variant(),
+            );
+            bind.set_value(
+                "scaling_algorithm".to_godot(),
+                self.scaling_algorithm.to_variant(),
+            );
+        });
     }
 
     fn deserialize(&mut self) {
-        let Some(mut load) = SaveDataBuilder::try_load_file(GRAPHICS_SAVE_PATH.into_godot()) else {
-            return;
-        };
-        // use_ssao: bool,
-        // use_bloom: bool,
-        // use_sdfgi: bool,
-        // use_ssil: bool,
-        // use_ssr: bool,
-        // value_brightness: f32,
-        // value_contrast: f32,
-        // value_saturation: f32,
-        // value_exposure: f32,
-        // window_fullscreen_mode: i32,
-        let mut bind = load.bind_mut();
-        self.use_ssao = bind.internal_get_value("use_ssao".to_godot(), self.use_ssao.to_godot());
-        self.use_bloom = bind.internal_get_value("use_bloom".to_godot(), self.use_bloom.to_godot());
-        self.use_sdfgi = bind.internal_get_value("use_sdfgi".to_godot(), self.use_sdfgi.to_godot());
-        self.use_ssil = bind.internal_get_value("use_ssil".to_godot(), self.use_ssil.to_godot());
-        self.use_ssr = bind.internal_get_value("use_ssr".to_godot(), self.use_ssr.to_godot());
-        self.value_brightness =
-            bind.internal_get_value("value_brightness".to_godot(), self.value_brightness);
-        self.value_contrast =
-            bind.internal_get_value("value_contrast".to_godot(), self.value_contrast);
-        self.value_saturation =
-            bind.internal_get_value("value_saturation".to_godot(), self.value_saturation);
-        self.value_exposure =
-            bind.internal_get_value("value_exposure".to_godot(), self.value_exposure);
-        self.window_fullscreen_mode = bind.internal_get_value(
-            "window_fullscreen_mode".to_godot(),
-            self.window_fullscreen_mode,
-        );
+        Self::read_settings_file(|bind| {
+            // use_ssao: bool,
+            // use_bloom: bool,
+            // use_sdfgi: bool,
+            // use_ssil: bool,
+            // use_ssr: bool,
+            // value_brightness: f32,
+            // value_contrast: f32,
+            // value_saturation: f32,
+            // value_exposure: f32,
+            // window_fullscreen_mode: i32,
+            self.use_ssao =
+                bind.internal_get_value("use_ssao".to_godot(), self.use_ssao.to_godot());
+            self.use_bloom =
+                bind.internal_get_value("use_bloom".to_godot(), self.use_bloom.to_godot());
+            self.use_sdfgi =
+                bind.internal_get_value("use_sdfgi".to_godot(), self.use_sdfgi.to_godot());
+            self.use_ssil =
+                bind.internal_get_value("use_ssil".to_godot(), self.use_ssil.to_godot());
+            self.use_ssr = bind.internal_get_value("use_ssr".to_godot(), self.use_ssr.to_godot());
+            self.value_brightness =
+                bind.internal_get_value("value_brightness".to_godot(), self.value_brightness);
+            self.value_contrast =
+                bind.internal_get_value("value_contrast".to_godot(), self.value_contrast);
+            self.value_saturation =
+                bind.internal_get_value("value_saturation".to_godot(), self.value_saturation);
+            self.value_exposure =
+                bind.internal_get_value("value_exposure".to_godot(), self.value_exposure);
+            self.window_fullscreen_mode = bind.internal_get_value(
+                "window_fullscreen_mode".to_godot(),
+                self.window_fullscreen_mode,
+            );
+        });
     }
 }
@@ -0,0 +1,124 @@
+use godot::{engine::Curve, prelude::*};
+
+use crate::scene::serialization::{SettingsFile, SqoreSerialized};
+
+/// A named rumble effect, played through the [HapticsManager](crate::scene::input::haptics_manager::HapticsManager)
+#[derive(GodotClass)]
+#[class(tool, base=Resource)]
+pub struct RumblePreset {
+    #[export]
+    pub name: StringName,
+    /// Strength of the high frequency motor
+    #[export(range=(0.0, 1.0))]
+    pub weak_magnitude: f32,
+    /// Strength of the low frequency motor
+    #[export(range=(0.0, 1.0))]
+    pub strong_magnitude: f32,
+    /// Seconds the effect lasts
+    #[export]
+    pub duration: f64,
+    /// Scales `weak_magnitude` over the effect, sampled from 0 at the start to 1 at the end. Constant if unset
+    #[export]
+    pub weak_curve: Option<Gd<Curve>>,
+    /// Scales `strong_magnitude` over the effect, sampled from 0 at the start to 1 at the end. Constant if unset
+    #[export]
+    pub strong_curve: Option<Gd<Curve>>,
+
+    base: Base<Resource>,
+}
+
+#[godot_api]
+impl IResource for RumblePreset {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            name: StringName::default(),
+            weak_magnitude: 0.5,
+            strong_magnitude: 0.5,
+            duration: 0.2,
+            weak_curve: None,
+            strong_curve: None,
+            base,
+        }
+    }
+}
+
+#[godot_api]
+impl RumblePreset {
+    /// The motor strengths at the given time since the effect started
+    pub fn sample(&self, elapsed: f64) -> (f32, f32) {
+        let progress = if self.duration > 0.0 {
+            (elapsed / self.duration).clamp(0.0, 1.0) as f32
+        } else {
+            1.0
+        };
+        let scale = |curve: &Option<Gd<Curve>>| match curve {
+            Some(curve) => curve.sample_baked(progress),
+            None => 1.0,
+        };
+        (
+            self.weak_magnitude * scale(&self.weak_curve),
+            self.strong_magnitude * scale(&self.strong_curve),
+        )
+    }
+}
+
+#[derive(GodotClass)]
+#[class(tool, base=Resource)]
+pub struct GameHapticsSettings {
+    /// Player setting, turns off all rumble when false
+    #[export]
+    pub enabled: bool,
+    /// Player setting, multiplies the strength of every effect
+    #[export(range=(0.0, 1.0))]
+    pub intensity: f32,
+    #[export]
+    pub presets: Array<Gd<RumblePreset>>,
+
+    base: Base<Resource>,
+}
+
+#[godot_api]
+impl IResource for GameHapticsSettings {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            enabled: true,
+            intensity: 1.0,
+            presets: Array::new(),
+            base,
+        }
+    }
+}
+
+#[godot_api]
+impl GameHapticsSettings {
+    #[func]
+    pub fn get_preset(&self, name: StringName) -> Option<Gd<RumblePreset>> {
+        self.presets
+            .iter_shared()
+            .find(|preset| preset.bind().name == name)
+    }
+}
+
+impl SettingsFile for GameHapticsSettings {
+    const SAVE_PATH: &'static str = "user://core/haptics.json";
+    const SCHEMA: &'static str = "haptics";
+    const SCHEMA_VERSION: i64 = 1;
+}
+
+impl SqoreSerialized for GameHapticsSettings {
+    fn serialize(&mut self) {
+        Self::write_settings_file(|sbind| {
+            sbind.set_value("enabled".to_godot(), self.enabled.to_variant());
+            sbind.set_value("intensity".to_godot(), self.intensity.to_variant());
+        });
+    }
+
+    fn deserialize(&mut self) {
+        Self::read_settings_file(|sbind| {
+            self.enabled = sbind.internal_get_value("enabled".to_godot(), self.enabled);
+            self.intensity = sbind
+                .internal_get_value("intensity".to_godot(), self.intensity)
+                .clamp(0.0, 1.0);
+        });
+    }
+}
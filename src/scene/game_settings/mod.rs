@@ -1,18 +1,26 @@
 use godot::prelude::*;
 
 use self::{
-    audio::GameAudioSettings, controls::GameControlsSettings, gameplay::GameGameplaySettings,
-    graphics::GameGraphicsSettings, user_mods::UserModifications,
+    audio::GameAudioSettings, axis::GameAxisSettings, controls::GameControlsSettings,
+    gameplay::GameGameplaySettings, graphics::GameGraphicsSettings, haptics::GameHapticsSettings,
+    user_mods::UserModifications,
 };
 use super::dialog::dialog_settings::DialogSettings;
-use crate::scene::{serialization::SqoreSerialized, vfx_stack::vfx_stack_resource::VFXStack};
+use crate::scene::{
+    input::{input_context::InputContext, input_glyphs::InputGlyphs},
+    serialization::SqoreSerialized,
+    vfx_stack::vfx_stack_resource::VFXStack,
+};
 
 pub mod accessibility;
 pub mod audio;
+pub mod axis;
 pub mod controls;
 pub mod effects;
 pub mod gameplay;
 pub mod graphics;
+pub mod haptics;
+pub mod saved_binds;
 pub mod user_mods;
 
 #[derive(GodotClass)]
@@ -23,15 +31,24 @@ pub struct SqoreConfig {
     #[export]
     pub controls: Option<Gd<GameControlsSettings>>,
     #[export]
+    pub axes: Option<Gd<GameAxisSettings>>,
+    #[export]
     pub gameplay: Option<Gd<GameGameplaySettings>>,
     #[export]
     pub audio: Option<Gd<GameAudioSettings>>,
     #[export]
+    pub haptics: Option<Gd<GameHapticsSettings>>,
+    #[export]
     pub vfx_stack: Option<Gd<VFXStack>>,
     #[export]
     pub dialog: Option<Gd<DialogSettings>>,
     #[export]
     pub user_mods: Option<Gd<UserModifications>>,
+    #[export]
+    pub input_glyphs: Option<Gd<InputGlyphs>>,
+    /// Contexts that can be pushed by name onto the `InputContextStack`
+    #[export]
+    pub input_contexts: Array<Gd<InputContext>>,
 
     //
     base: Base<Resource>,
@@ -42,6 +59,7 @@ impl IResource for SqoreConfig {}
 #[godot_api]
 impl SqoreConfig {}
 
+/// Gameplay settings and the VFX stack are not part of this, `Sqore` saves them as participants
 impl SqoreSerialized for SqoreConfig {
     fn serialize(&mut self) {
         if let Some(mut gfx) = self.graphics.clone() {
@@ -50,15 +68,15 @@ impl SqoreSerialized for SqoreConfig {
         if let Some(mut controls) = self.controls.clone() {
             controls.bind_mut().serialize();
         }
+        if let Some(mut axes) = self.axes.clone() {
+            axes.bind_mut().serialize();
+        }
         if let Some(mut audio) = self.audio.clone() {
             audio.bind_mut().serialize();
         }
-        // if let Some(mut gameplay) = self.gameplay {
-        // 	gameplay.bind_mut().serialize();
-        // }
-        // if let Some(mut vfx) = self.vfx_stack {
-        // 	vfx.bind_mut().serialize();
-        // }
+        if let Some(mut haptics) = self.haptics.clone() {
+            haptics.bind_mut().serialize();
+        }
     }
 
     fn deserialize(&mut self) {
@@ -68,14 +86,14 @@ impl SqoreSerialized for SqoreConfig {
         if let Some(mut controls) = self.controls.clone() {
             controls.bind_mut().deserialize()
         }
+        if let Some(mut axes) = self.axes.clone() {
+            axes.bind_mut().deserialize()
+        }
         if let Some(mut audio) = self.audio.clone() {
             audio.bind_mut().deserialize()
         }
-        // if let Some(mut gameplay) = self.gameplay {
-        // 	gameplay.bind_mut().deserialize();
-        // }
-        // if let Some(mut vfx) = self.vfx_stack {
-        // 	vfx.bind_mut().deserialize();
-        // }
+        if let Some(mut haptics) = self.haptics.clone() {
+            haptics.bind_mut().deserialize()
+        }
     }
 }
@@ -0,0 +1,235 @@
+//! How input binds are stored in the controls settings file.
+//!
+//! Only uses `std`, so the format can be tested without the engine. [GameControlsSettings](super::controls::GameControlsSettings) converts between input events and [SavedBind], and between fields and dictionaries. Plain JSON reads every number back as a float, so integer fields accept floats as well.
+
+/// One field of a saved bind, as it appears in the settings file
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl Field {
+    fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(value) => Some(*value),
+            Self::Float(value) => Some(*value as i64),
+            Self::Text(_) => None,
+        }
+    }
+
+    fn as_float(&self) -> Option<f64> {
+        match self {
+            Self::Int(value) => Some(*value as f64),
+            Self::Float(value) => Some(*value),
+            Self::Text(_) => None,
+        }
+    }
+
+    fn as_text(&self) -> Option<&str> {
+        match self {
+            Self::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+const KEY_TYPE: &str = "type";
+const TYPE_JOYPAD_BUTTON: i64 = 0;
+const TYPE_JOYPAD_MOTION: i64 = 1;
+const TYPE_KEY: i64 = 2;
+const TYPE_MOUSE_BUTTON: i64 = 3;
+/// Any other event, identified by its class name
+const TYPE_GENERIC: i64 = 4;
+const CLASS_ACTION: &str = "InputEventAction";
+
+/// The parts of an input event that matter for matching. Binds match every device and have no pressed state, so neither is stored
+#[derive(Debug, Clone, PartialEq)]
+pub enum SavedBind {
+    JoypadButton {
+        button_index: i64,
+    },
+    JoypadMotion {
+        axis: i64,
+        /// Only the sign matters
+        axis_value: f64,
+    },
+    Key {
+        keycode: i64,
+        physical_keycode: i64,
+        key_label: i64,
+        unicode: i64,
+    },
+    MouseButton {
+        button_index: i64,
+    },
+    Action {
+        action: String,
+        strength: f64,
+    },
+}
+
+impl SavedBind {
+    pub fn to_fields(&self) -> Vec<(&'static str, Field)> {
+        match self {
+            Self::JoypadButton { button_index } => vec![
+                (KEY_TYPE, Field::Int(TYPE_JOYPAD_BUTTON)),
+                ("button_index", Field::Int(*button_index)),
+            ],
+            Self::JoypadMotion { axis, axis_value } => vec![
+                (KEY_TYPE, Field::Int(TYPE_JOYPAD_MOTION)),
+                ("axis", Field::Int(*axis)),
+                ("axis_value", Field::Float(*axis_value)),
+            ],
+            Self::Key {
+                keycode,
+                physical_keycode,
+                key_label,
+                unicode,
+            } => vec![
+                (KEY_TYPE, Field::Int(TYPE_KEY)),
+                ("keycode", Field::Int(*keycode)),
+                ("physical_keycode", Field::Int(*physical_keycode)),
+                ("key_label", Field::Int(*key_label)),
+                ("unicode", Field::Int(*unicode)),
+            ],
+            Self::MouseButton { button_index } => vec![
+                (KEY_TYPE, Field::Int(TYPE_MOUSE_BUTTON)),
+                ("button_index", Field::Int(*button_index)),
+            ],
+            Self::Action { action, strength } => vec![
+                (KEY_TYPE, Field::Int(TYPE_GENERIC)),
+                ("class", Field::Text(CLASS_ACTION.to_string())),
+                ("action", Field::Text(action.clone())),
+                ("strength", Field::Float(*strength)),
+            ],
+        }
+    }
+
+    /// Reads a bind back from its fields. Fields written by older versions (such as `pressed`) are ignored
+    pub fn from_fields(get: impl Fn(&str) -> Option<Field>) -> Result<Self, String> {
+        let int = |key: &str| {
+            get(key)
+                .and_then(|field| field.as_int())
+                .ok_or_else(|| format!("missing or invalid \"{}\"", key))
+        };
+        let float = |key: &str| {
+            get(key)
+                .and_then(|field| field.as_float())
+                .ok_or_else(|| format!("missing or invalid \"{}\"", key))
+        };
+        match int(KEY_TYPE)? {
+            TYPE_JOYPAD_BUTTON => Ok(Self::JoypadButton {
+                button_index: int("button_index")?,
+            }),
+            TYPE_JOYPAD_MOTION => Ok(Self::JoypadMotion {
+                axis: int("axis")?,
+                axis_value: float("axis_value")?,
+            }),
+            TYPE_KEY => Ok(Self::Key {
+                keycode: int("keycode")?,
+                physical_keycode: int("physical_keycode")?,
+                key_label: int("key_label")?,
+                unicode: int("unicode")?,
+            }),
+            TYPE_MOUSE_BUTTON => Ok(Self::MouseButton {
+                button_index: int("button_index")?,
+            }),
+            TYPE_GENERIC => {
+                let class = get("class").and_then(|field| field.as_text().map(String::from));
+                if class.as_deref() != Some(CLASS_ACTION) {
+                    return Err(format!(
+                        "unsupported input event \"{}\"",
+                        class.unwrap_or_default()
+                    ));
+                }
+                let action = get("action")
+                    .and_then(|field| field.as_text().map(String::from))
+                    .ok_or_else(|| String::from("missing or invalid \"action\""))?;
+                Ok(Self::Action {
+                    action,
+                    strength: float("strength")?,
+                })
+            }
+            other => Err(format!("unknown input event type {}", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Writes the fields the way plain JSON reads them back, with every number as a float
+    fn through_plain_json(bind: &SavedBind) -> Result<SavedBind, String> {
+        let fields: HashMap<&str, Field> = bind
+            .to_fields()
+            .into_iter()
+            .map(|(key, field)| match field {
+                Field::Int(value) => (key, Field::Float(value as f64)),
+                other => (key, other),
+            })
+            .collect();
+        SavedBind::from_fields(|key| fields.get(key).cloned())
+    }
+
+    #[test]
+    fn key_bind_round_trips() {
+        let bind = SavedBind::Key {
+            keycode: 69,
+            physical_keycode: 69,
+            key_label: 69,
+            unicode: 101,
+        };
+        assert_eq!(through_plain_json(&bind), Ok(bind));
+    }
+
+    #[test]
+    fn joypad_binds_round_trip() {
+        let button = SavedBind::JoypadButton { button_index: 3 };
+        assert_eq!(through_plain_json(&button), Ok(button));
+        let motion = SavedBind::JoypadMotion {
+            axis: 5,
+            axis_value: -1.0,
+        };
+        assert_eq!(through_plain_json(&motion), Ok(motion));
+    }
+
+    #[test]
+    fn action_bind_round_trips() {
+        let bind = SavedBind::Action {
+            action: String::from("jump"),
+            strength: 1.0,
+        };
+        assert_eq!(through_plain_json(&bind), Ok(bind));
+    }
+
+    #[test]
+    fn reads_binds_written_by_older_versions() {
+        let fields: HashMap<&str, Field> = [
+            ("type", Field::Float(0.0)),
+            ("button_index", Field::Float(1.0)),
+            ("pressure", Field::Float(0.0)),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            SavedBind::from_fields(|key| fields.get(key).cloned()),
+            Ok(SavedBind::JoypadButton { button_index: 1 })
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_events() {
+        let fields: HashMap<&str, Field> = [
+            ("type", Field::Float(4.0)),
+            ("class", Field::Text(String::from("InputEventMouseMotion"))),
+        ]
+        .into_iter()
+        .collect();
+        assert!(SavedBind::from_fields(|key| fields.get(key).cloned()).is_err());
+        assert!(SavedBind::from_fields(|_| None).is_err());
+    }
+}
@@ -1,2 +1,3 @@
 pub mod collapsable_container;
-pub mod labelled_hslider;
\ No newline at end of file
+pub mod labelled_hslider;
+pub mod remap_menu;
\ No newline at end of file
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+
+use godot::{
+    engine::{
+        control::SizeFlags, Button, HBoxContainer, IVBoxContainer, InputEvent, Label, VBoxContainer,
+    },
+    prelude::*,
+};
+
+use crate::scene::{
+    game_globals::Sqore,
+    game_settings::controls::GameControlsSettings,
+    input::{
+        input_device::{InputDeviceClass, InputDeviceMonitor},
+        input_labels::{self, JoypadStyle},
+    },
+};
+
+/// A ready-made controls menu. Lists every action the player may rebind (see `GameControlsSettings.can_bind`), with a button per bind slot that waits for the next input when pressed.
+///
+/// Every control it creates uses a theme type variation, so the menu can be styled from a theme: `RemapMenuRow`, `RemapMenuActionLabel`, `RemapMenuBindButton`, `RemapMenuResetButton`, and `RemapMenuConflictLabel`.
+#[derive(GodotClass)]
+#[class(base=VBoxContainer)]
+pub struct RemapMenu {
+    /// How many binds are shown (and can be set) per action
+    #[export]
+    slots_per_action: i32,
+    /// Seconds to wait for input before the rebind is cancelled. Zero waits forever
+    #[export]
+    rebind_timeout: f64,
+    /// Display names for actions. Actions without an entry are shown as their name with underscores replaced by spaces
+    #[export]
+    action_names: Dictionary,
+    #[export]
+    waiting_text: GString,
+    #[export]
+    unbound_text: GString,
+    #[export]
+    reset_text: GString,
+    #[export]
+    reset_all_text: GString,
+    /// Shown when a new bind is already used by other actions. `{action}` and `{actions}` are replaced with the rebound action and the conflicting ones
+    #[export]
+    conflict_text: GString,
+
+    settings: Option<Gd<GameControlsSettings>>,
+    bind_buttons: HashMap<(GString, i32), Gd<Button>>,
+    conflict_label: Option<Gd<Label>>,
+    /// The slot currently waiting for input
+    pending: Option<(GString, i32)>,
+    /// Increased for every rebind, so the timeout of an earlier rebind doesn't cancel a later one
+    rebind_id: i64,
+    base: Base<VBoxContainer>,
+}
+
+#[godot_api]
+impl IVBoxContainer for RemapMenu {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            slots_per_action: 2,
+            rebind_timeout: 5.0,
+            action_names: Dictionary::new(),
+            waiting_text: "Press any input...".into(),
+            unbound_text: "Unbound".into(),
+            reset_text: "Reset".into(),
+            reset_all_text: "Reset All".into(),
+            conflict_text: "{action} is also bound to: {actions}".into(),
+            settings: None,
+            bind_buttons: HashMap::new(),
+            conflict_label: None,
+            pending: None,
+            rebind_id: 0,
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        let Some(mut settings) = Sqore::singleton().bind().get_config().bind().get_controls()
+        else {
+            godot_warn!("RemapMenu requires controls settings in the Sqore config");
+            return;
+        };
+        for (signal, method) in [
+            (
+                GameControlsSettings::SIGNAL_BINDS_CHANGED,
+                "on_binds_changed",
+            ),
+            (
+                GameControlsSettings::SIGNAL_REBIND_FINISHED,
+                "on_rebind_finished",
+            ),
+            (
+                GameControlsSettings::SIGNAL_REBIND_CANCELLED,
+                "on_rebind_cancelled",
+            ),
+            (
+                GameControlsSettings::SIGNAL_BIND_CONFLICT,
+                "on_bind_conflict",
+            ),
+        ] {
+            settings.connect(
+                StringName::from(signal),
+                Callable::from_object_method(&self.to_gd(), method),
+            );
+        }
+        self.settings = Some(settings);
+        // joypad buttons are named after the vendor of the last used joypad
+        let mut monitor = InputDeviceMonitor::singleton();
+        monitor.bind_mut().start_watching();
+        monitor.connect(
+            StringName::from(InputDeviceMonitor::SIGNAL_INPUT_DEVICE_CHANGED),
+            Callable::from_object_method(&self.to_gd(), "on_input_device_changed"),
+        );
+        self.rebuild();
+    }
+
+    fn exit_tree(&mut self) {
+        if let Some(settings) = &mut self.settings {
+            if settings.bind().is_rebinding() {
+                settings.bind_mut().cancel_rebind();
+            }
+        }
+    }
+}
+
+#[godot_api]
+impl RemapMenu {
+    /// Recreates the rows, such as after changing the exported settings
+    #[func]
+    pub fn rebuild(&mut self) {
+        for mut child in self.base().get_children().iter_shared() {
+            child.queue_free();
+        }
+        self.bind_buttons.clear();
+        let Some(settings) = self.settings.clone() else {
+            return;
+        };
+
+        for action in settings.bind().get_bindable_actions().iter_shared() {
+            let mut row = HBoxContainer::new_alloc();
+            row.set_theme_type_variation("RemapMenuRow".into());
+
+            let mut label = Label::new_alloc();
+            label.set_theme_type_variation("RemapMenuActionLabel".into());
+            label.set_text(self.get_action_name(&action));
+            label.set_h_size_flags(SizeFlags::EXPAND_FILL);
+            row.add_child(label.upcast());
+
+            for slot in 0..self.slots_per_action {
+                let mut button = Button::new_alloc();
+                button.set_theme_type_variation("RemapMenuBindButton".into());
+                button.set_h_size_flags(SizeFlags::EXPAND_FILL);
+                button.connect(
+                    StringName::from("pressed"),
+                    Callable::from_object_method(&self.to_gd(), "on_bind_pressed")
+                        .bindv(varray![action.clone(), slot]),
+                );
+                row.add_child(button.clone().upcast());
+                self.bind_buttons.insert((action.clone(), slot), button);
+            }
+
+            let mut reset = Button::new_alloc();
+            reset.set_theme_type_variation("RemapMenuResetButton".into());
+            reset.set_text(self.reset_text.clone());
+            reset.connect(
+                StringName::from("pressed"),
+                Callable::from_object_method(&self.to_gd(), "on_reset_pressed")
+                    .bindv(varray![action.clone()]),
+            );
+            row.add_child(reset.upcast());
+
+            self.base_mut().add_child(row.upcast());
+            self.refresh_action(action);
+        }
+
+        let mut conflict_label = Label::new_alloc();
+        conflict_label.set_theme_type_variation("RemapMenuConflictLabel".into());
+        conflict_label.set_visible(false);
+        self.base_mut().add_child(conflict_label.clone().upcast());
+        self.conflict_label = Some(conflict_label);
+
+        let mut reset_all = Button::new_alloc();
+        reset_all.set_theme_type_variation("RemapMenuResetButton".into());
+        reset_all.set_text(self.reset_all_text.clone());
+        reset_all.connect(
+            StringName::from("pressed"),
+            Callable::from_object_method(&self.to_gd(), "on_reset_all_pressed"),
+        );
+        self.base_mut().add_child(reset_all.upcast());
+    }
+
+    /// Updates the bind buttons of the action to its current binds
+    #[func]
+    pub fn refresh_action(&mut self, action: GString) {
+        let Some(settings) = self.settings.clone() else {
+            return;
+        };
+        let style = InputDeviceMonitor::singleton()
+            .bind_mut()
+            .get_joypad_style();
+        for slot in 0..self.slots_per_action {
+            let Some(button) = self.bind_buttons.get_mut(&(action.clone(), slot)) else {
+                continue;
+            };
+            let waiting = self.pending.as_ref() == Some(&(action.clone(), slot));
+            let text = match settings.bind().get_bind(action.clone(), slot) {
+                _ if waiting => self.waiting_text.clone(),
+                Some(event) => input_labels::get_event_label(&event, style).to_godot(),
+                None => self.unbound_text.clone(),
+            };
+            button.set_text(text);
+        }
+    }
+
+    /// The name of the event as shown in the menu, based on the last used joypad
+    #[func]
+    pub fn get_event_label(event: Gd<InputEvent>) -> GString {
+        let style = InputDeviceMonitor::singleton()
+            .bind_mut()
+            .get_joypad_style();
+        input_labels::get_event_label(&event, style).to_godot()
+    }
+
+    #[func]
+    fn on_bind_pressed(&mut self, action: GString, slot: i32) {
+        let Some(mut settings) = self.settings.clone() else {
+            return;
+        };
+        // starting a rebind cancels the previous one
+        if !settings.bind_mut().start_rebind(action.clone(), slot) {
+            return;
+        }
+        if let Some(label) = &mut self.conflict_label {
+            label.set_visible(false);
+        }
+        let previous = self.pending.replace((action.clone(), slot));
+        if let Some((previous_action, _)) = previous {
+            self.refresh_action(previous_action);
+        }
+        self.refresh_action(action);
+
+        self.rebind_id += 1;
+        if self.rebind_timeout <= 0.0 {
+            return;
+        }
+        let Some(mut tree) = self.base().get_tree() else {
+            return;
+        };
+        let Some(mut timer) = tree.create_timer(self.rebind_timeout) else {
+            return;
+        };
+        timer.connect(
+            StringName::from("timeout"),
+            Callable::from_object_method(&self.to_gd(), "on_rebind_timeout")
+                .bindv(varray![self.rebind_id]),
+        );
+    }
+
+    #[func]
+    fn on_rebind_timeout(&mut self, rebind_id: i64) {
+        if rebind_id != self.rebind_id {
+            return;
+        }
+        if let Some(settings) = &mut self.settings {
+            if settings.bind().is_rebinding() {
+                settings.bind_mut().cancel_rebind();
+            }
+        }
+    }
+
+    #[func]
+    fn on_reset_pressed(&mut self, action: GString) {
+        if let Some(settings) = &mut self.settings {
+            settings.bind_mut().reset_action(action);
+        }
+    }
+
+    #[func]
+    fn on_reset_all_pressed(&mut self) {
+        if let Some(settings) = &mut self.settings {
+            settings.bind_mut().reset_all();
+        }
+        if let Some(label) = &mut self.conflict_label {
+            label.set_visible(false);
+        }
+    }
+
+    #[func]
+    fn on_binds_changed(&mut self, action: GString) {
+        self.refresh_action(action);
+    }
+
+    #[func]
+    fn on_rebind_finished(&mut self, action: GString, slot: i32, _event: Gd<InputEvent>) {
+        self.finish_pending(action, slot);
+    }
+
+    #[func]
+    fn on_rebind_cancelled(&mut self, action: GString, slot: i32) {
+        self.finish_pending(action, slot);
+    }
+
+    #[func]
+    fn on_input_device_changed(&mut self, _device: InputDeviceClass, _style: JoypadStyle) {
+        let actions: Vec<GString> = self
+            .bind_buttons
+            .keys()
+            .filter(|(_, slot)| *slot == 0)
+            .map(|(action, _)| action.clone())
+            .collect();
+        for action in actions {
+            self.refresh_action(action);
+        }
+    }
+
+    fn finish_pending(&mut self, action: GString, slot: i32) {
+        // a cancelled rebind may be followed by a new one that already started
+        if self.pending.as_ref() == Some(&(action.clone(), slot)) {
+            self.pending = None;
+        }
+        self.refresh_action(action);
+    }
+
+    #[func]
+    fn on_bind_conflict(
+        &mut self,
+        action: GString,
+        _event: Gd<InputEvent>,
+        conflicting_actions: Array<GString>,
+    ) {
+        let names: Vec<String> = conflicting_actions
+            .iter_shared()
+            .map(|other| self.get_action_name(&other).to_string())
+            .collect();
+        let text = self
+            .conflict_text
+            .to_string()
+            .replace("{action}", &self.get_action_name(&action).to_string())
+            .replace("{actions}", &names.join(", "));
+        if let Some(label) = &mut self.conflict_label {
+            label.set_text(text.to_godot());
+            label.set_visible(true);
+        }
+    }
+
+    fn get_action_name(&self, action: &GString) -> GString {
+        match self.action_names.get(action.clone()) {
+            Some(name) => GString::try_from_variant(&name).unwrap_or(action.clone()),
+            None => action.to_string().replace('_', " ").to_godot(),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Controller rumble built from [RumblePreset] resources.
+//!
+//! Effects layer on top of each other: the motor strengths of every running effect are added up (and capped at full strength) each frame, then scaled by the player's intensity setting from [GameHapticsSettings].
+
+use std::collections::HashMap;
+
+use godot::{
+    engine::{Engine, Time},
+    prelude::*,
+};
+
+use super::input_device::InputDeviceMonitor;
+use crate::{
+    scene::{
+        game_globals::Sqore,
+        game_settings::haptics::{GameHapticsSettings, RumblePreset},
+    },
+    util::SqoreUtil,
+};
+
+/// Plays on the last used joypad, or every connected joypad if none was used yet
+pub const DEVICE_ACTIVE: i32 = -1;
+
+pub fn register_singleton() {
+    Engine::singleton().register_singleton(
+        StringName::from(HapticsManager::SINGLETON_NAME),
+        HapticsManager::new_alloc().upcast(),
+    );
+}
+
+pub fn unregister_singleton() {
+    Engine::singleton().unregister_singleton(StringName::from(HapticsManager::SINGLETON_NAME));
+}
+
+struct ActiveEffect {
+    id: i64,
+    preset: Gd<RumblePreset>,
+    devices: Vec<i32>,
+    elapsed: f64,
+}
+
+/// Plays rumble presets on joypads. Named presets are looked up in the `haptics` settings of the Sqore config
+#[derive(GodotClass)]
+#[class(base=Object)]
+pub struct HapticsManager {
+    effects: Vec<ActiveEffect>,
+    next_id: i64,
+    /// The motor strengths last sent to each device, so unchanged strengths aren't sent again every frame
+    applied: HashMap<i32, (f32, f32)>,
+    last_update_usec: u64,
+    is_updating: bool,
+    base: Base<Object>,
+}
+
+#[godot_api]
+impl IObject for HapticsManager {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            effects: Vec::new(),
+            next_id: 1,
+            applied: HashMap::new(),
+            last_update_usec: 0,
+            is_updating: false,
+            base,
+        }
+    }
+}
+
+#[godot_api]
+impl HapticsManager {
+    pub const SINGLETON_NAME: &'static str = "HapticsManager";
+
+    /// Plays the preset with the given name on the last used joypad. Returns an id for `stop`, or -1 if nothing is played
+    #[func]
+    pub fn play(&mut self, preset_name: StringName) -> i64 {
+        self.play_on_device(preset_name, DEVICE_ACTIVE)
+    }
+
+    #[func]
+    pub fn play_on_device(&mut self, preset_name: StringName, device: i32) -> i64 {
+        let Some(settings) = Self::get_settings() else {
+            godot_warn!(
+                "Cannot play rumble preset \"{}\" without haptics settings in the Sqore config",
+                preset_name
+            );
+            return -1;
+        };
+        let Some(preset) = settings.bind().get_preset(preset_name.clone()) else {
+            godot_warn!("Unknown rumble preset \"{}\"", preset_name);
+            return -1;
+        };
+        self.play_preset(preset, device)
+    }
+
+    /// Plays a preset that isn't registered in the settings
+    #[func]
+    pub fn play_preset(&mut self, preset: Gd<RumblePreset>, device: i32) -> i64 {
+        let enabled = Self::get_settings().map_or(true, |settings| settings.bind().enabled);
+        if !enabled {
+            return -1;
+        }
+        let devices = Self::resolve_devices(device);
+        if devices.is_empty() {
+            return -1;
+        }
+        self.start_updating();
+        let id = self.next_id;
+        self.next_id += 1;
+        self.effects.push(ActiveEffect {
+            id,
+            preset,
+            devices,
+            elapsed: 0.0,
+        });
+        id
+    }
+
+    #[func]
+    pub fn stop(&mut self, id: i64) {
+        self.effects.retain(|effect| effect.id != id);
+        self.apply();
+    }
+
+    #[func]
+    pub fn stop_all(&mut self) {
+        self.effects.clear();
+        self.apply();
+    }
+
+    #[func]
+    pub fn is_playing(&self, id: i64) -> bool {
+        self.effects.iter().any(|effect| effect.id == id)
+    }
+
+    /// Advances every effect, called each frame while the manager is in use
+    #[func]
+    fn update(&mut self) {
+        let now = Time::singleton().get_ticks_usec();
+        let delta = now.saturating_sub(self.last_update_usec) as f64 / 1_000_000.0;
+        self.last_update_usec = now;
+        for effect in self.effects.iter_mut() {
+            effect.elapsed += delta;
+        }
+        self.effects
+            .retain(|effect| effect.elapsed < effect.preset.bind().duration);
+        self.apply();
+    }
+
+    fn apply(&mut self) {
+        let intensity = match Self::get_settings() {
+            Some(settings) if settings.bind().enabled => settings.bind().intensity,
+            Some(_) => 0.0,
+            None => 1.0,
+        };
+        let mut totals: HashMap<i32, (f32, f32)> = HashMap::new();
+        for effect in self.effects.iter() {
+            let (weak, strong) = effect.preset.bind().sample(effect.elapsed);
+            for device in effect.devices.iter() {
+                let total = totals.entry(*device).or_default();
+                total.0 += weak;
+                total.1 += strong;
+            }
+        }
+        let mut input = Input::singleton();
+        for (device, (weak, strong)) in totals.iter() {
+            let strength = (weak.min(1.0) * intensity, strong.min(1.0) * intensity);
+            if self.applied.get(device) == Some(&strength) {
+                continue;
+            }
+            // a duration of zero keeps the motors going until the next change
+            input.start_joy_vibration(*device, strength.0, strength.1);
+            self.applied.insert(*device, strength);
+        }
+        self.applied.retain(|device, _| {
+            let is_active = totals.contains_key(device);
+            if !is_active {
+                input.stop_joy_vibration(*device);
+            }
+            is_active
+        });
+    }
+
+    /// Connects to the frame signal of the scene tree, since singletons don't get processed on their own
+    fn start_updating(&mut self) {
+        if self.is_updating {
+            return;
+        }
+        let Some(mut tree) = SqoreUtil::get_scene_tree_global() else {
+            return;
+        };
+        self.last_update_usec = Time::singleton().get_ticks_usec();
+        tree.connect(
+            StringName::from("process_frame"),
+            Callable::from_object_method(&self.to_gd(), "update"),
+        );
+        self.is_updating = true;
+    }
+
+    fn resolve_devices(device: i32) -> Vec<i32> {
+        if device != DEVICE_ACTIVE {
+            return vec![device];
+        }
+        let last_used = InputDeviceMonitor::singleton().bind().get_joypad_device();
+        if last_used != DEVICE_ACTIVE {
+            return vec![last_used];
+        }
+        Input::singleton()
+            .get_connected_joypads()
+            .iter_shared()
+            .map(|device| device as i32)
+            .collect()
+    }
+
+    fn get_settings() -> Option<Gd<GameHapticsSettings>> {
+        Sqore::singleton().bind().get_config().bind().get_haptics()
+    }
+
+    pub fn singleton() -> Gd<HapticsManager> {
+        let Some(vol) = Engine::singleton().get_singleton(StringName::from(Self::SINGLETON_NAME))
+        else {
+            panic!("Failed to find engine singleton for HapticsManager. You must access this after it is registered!");
+        };
+        let res_core: Result<Gd<HapticsManager>, Gd<_>> = vol.try_cast();
+        let Ok(core) = res_core else {
+            panic!("Failed to cast engine singleton for HapticsManager. This should never happen!");
+        };
+        core
+    }
+}
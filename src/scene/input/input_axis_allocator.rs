@@ -1,8 +1,17 @@
 use godot::{
-    engine::{InputEvent, InputEventMouseMotion, InputEventPanGesture},
+    engine::{
+        global::JoyAxis, input::MouseMode, Curve, InputEvent, InputEventMouseMotion,
+        InputEventPanGesture,
+    },
     prelude::*,
 };
 
+use crate::scene::{
+    game_globals::Sqore,
+    game_settings::axis::{AxisPreference, GameAxisSettings},
+    input::input_device::InputDeviceMonitor,
+};
+
 enum AxisInput {
     MouseMotion,
     FourActionAxis {
@@ -13,6 +22,12 @@ enum AxisInput {
     },
     /// note: this is a two-finger pan
     TouchGesturePan,
+    /// a raw joypad stick. A negative device reads from the last used joypad
+    JoyAxis {
+        x: JoyAxis,
+        y: JoyAxis,
+        device: i32,
+    },
 }
 
 impl AxisInput {
@@ -30,6 +45,21 @@ impl AxisInput {
                 Input::singleton().get_vector(left.clone(), right.clone(), down.clone(), up.clone())
                     * delta
             }
+            AxisInput::JoyAxis { x, y, device } => {
+                let device = if *device < 0 {
+                    InputDeviceMonitor::singleton()
+                        .bind()
+                        .get_joypad_device()
+                        .max(0)
+                } else {
+                    *device
+                };
+                let input = Input::singleton();
+                Vector2::new(
+                    input.get_joy_axis(device, *x),
+                    input.get_joy_axis(device, *y),
+                ) * delta
+            }
         }
     }
     fn get_value_for(&self, event: &Gd<InputEvent>) -> Vector2 {
@@ -57,14 +87,65 @@ impl AxisInput {
                 down,
                 up,
             } => Vector2::ZERO,
+            AxisInput::JoyAxis { .. } => Vector2::ZERO,
         }
     }
+    /// whether the source reads a position (like a stick) rather than movement (like the mouse), which is what dead zones and curves apply to
+    fn is_positional(&self) -> bool {
+        matches!(
+            self,
+            AxisInput::FourActionAxis { .. } | AxisInput::JoyAxis { .. }
+        )
+    }
+}
+
+/// an input source along with its modifiers
+struct AxisSource {
+    input: AxisInput,
+    factor: f32,
+    dead_zone: f32,
+    curve: Option<Gd<Curve>>,
+    invert_x: bool,
+    invert_y: bool,
+    settings_key: GString,
+    preference: AxisPreference,
+    require_mouse_capture: bool,
+}
+
+impl AxisSource {
+    fn modify(&self, value: Vector2, delta: f32) -> Vector2 {
+        let mut value = value;
+        if self.input.is_positional() && delta > 0.0 {
+            value = self.shape(value / delta) * delta;
+        }
+        if self.invert_x != self.preference.invert_x {
+            value.x = -value.x;
+        }
+        if self.invert_y != self.preference.invert_y {
+            value.y = -value.y;
+        }
+        value * self.factor * self.preference.sensitivity
+    }
+
+    /// applies the radial dead zone and the response curve
+    fn shape(&self, position: Vector2) -> Vector2 {
+        let length = position.length();
+        if length <= self.dead_zone || length == 0.0 {
+            return Vector2::ZERO;
+        }
+        // rescale so movement starts from zero at the edge of the dead zone
+        let mut magnitude = ((length - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0);
+        if let Some(curve) = &self.curve {
+            magnitude = curve.sample_baked(magnitude);
+        }
+        position / length * magnitude
+    }
 }
 
 #[derive(GodotClass)]
 #[class(init, base=Resource)]
 pub struct InputAxisAllocatorResource {
-    #[export(enum=(MouseMotion=0, FourActionAxis=1, TouchGesturePan=2))]
+    #[export(enum=(MouseMotion=0, FourActionAxis=1, TouchGesturePan=2, JoyAxis=3))]
     input_type: u32,
     #[export]
     factor: f32,
@@ -76,6 +157,31 @@ pub struct InputAxisAllocatorResource {
     event_up: StringName,
     #[export]
     event_down: StringName,
+    /// the JoyAxis used for horizontal movement
+    #[export(enum=(LeftX=0, LeftY=1, RightX=2, RightY=3, TriggerLeft=4, TriggerRight=5))]
+    joy_axis_x: i32,
+    /// the JoyAxis used for vertical movement
+    #[export(enum=(LeftX=0, LeftY=1, RightX=2, RightY=3, TriggerLeft=4, TriggerRight=5))]
+    joy_axis_y: i32,
+    /// the joypad to read for JoyAxis. A negative value reads the last used joypad
+    #[export]
+    joy_device: i32,
+    /// only for FourActionAxis and JoyAxis. Stick positions below this length are ignored
+    #[export(range=(0.0, 0.95))]
+    dead_zone: f32,
+    /// only for FourActionAxis and JoyAxis. Maps how far the stick is pushed (past the dead zone) to output strength, both from 0 to 1
+    #[export]
+    response_curve: Option<Gd<Curve>>,
+    #[export]
+    invert_x: bool,
+    #[export]
+    invert_y: bool,
+    /// only for MouseMotion. Ignores movement unless the mouse is captured, so moving the cursor around a menu doesn't turn the camera
+    #[export]
+    require_mouse_capture: bool,
+    /// key of the player preferences (sensitivity and inversion) in the axis settings of the Sqore config. Player inversion flips on top of `invert_x` and `invert_y`
+    #[export]
+    settings_key: GString,
 
     node: Base<Resource>,
 }
@@ -90,7 +196,7 @@ impl InputAxisAllocatorResource {}
 pub struct InputAxisAllocator {
     #[export]
     events: Array<Gd<InputAxisAllocatorResource>>,
-    events_internal: Vec<(AxisInput, f32)>,
+    events_internal: Vec<AxisSource>,
 
     value: Vector2,
 
@@ -111,21 +217,68 @@ impl INode for InputAxisAllocator {
                     up: b.event_up.clone(),
                 },
                 2 => AxisInput::TouchGesturePan,
-                _ => unreachable!(),
+                3 => {
+                    let (Some(x), Some(y)) = (
+                        JoyAxis::try_from_ord(b.joy_axis_x),
+                        JoyAxis::try_from_ord(b.joy_axis_y),
+                    ) else {
+                        godot_warn!(
+                            "InputAxisAllocator found invalid joy axes {} and {}, skipping them",
+                            b.joy_axis_x,
+                            b.joy_axis_y
+                        );
+                        continue;
+                    };
+                    AxisInput::JoyAxis {
+                        x,
+                        y,
+                        device: b.joy_device,
+                    }
+                }
+                other => {
+                    godot_warn!(
+                        "InputAxisAllocator found unknown input_type {}, skipping it",
+                        other
+                    );
+                    continue;
+                }
             };
-            self.events_internal.push((axis, b.factor));
+            self.events_internal.push(AxisSource {
+                input: axis,
+                factor: b.factor,
+                dead_zone: b.dead_zone.clamp(0.0, 0.95),
+                curve: b.response_curve.clone(),
+                invert_x: b.invert_x,
+                invert_y: b.invert_y,
+                settings_key: b.settings_key.clone(),
+                preference: AxisPreference::default(),
+                require_mouse_capture: b.require_mouse_capture,
+            });
+        }
+
+        if let Some(mut settings) = Self::get_settings() {
+            settings.connect(
+                StringName::from(GameAxisSettings::SIGNAL_AXIS_SETTINGS_CHANGED),
+                Callable::from_object_method(&self.to_gd(), "on_axis_settings_changed"),
+            );
         }
+        self.refresh_settings();
     }
 
     fn process(&mut self, delta: f64) {
         for eve in self.events_internal.iter() {
-            self.value += eve.0.get_process_value(delta as f32) * eve.1;
+            let delta = delta as f32;
+            self.value += eve.modify(eve.input.get_process_value(delta), delta);
         }
     }
 
     fn input(&mut self, event: Gd<InputEvent>) {
+        let is_captured = Input::singleton().get_mouse_mode() == MouseMode::CAPTURED;
         for eve in self.events_internal.iter() {
-            self.value += eve.0.get_value_for(&event) * eve.1;
+            if eve.require_mouse_capture && !is_captured {
+                continue;
+            }
+            self.value += eve.modify(eve.input.get_value_for(&event), 0.0);
         }
     }
 }
@@ -138,4 +291,27 @@ impl InputAxisAllocator {
         self.value = Vector2::ZERO;
         ret
     }
+
+    /// reloads the player preferences of every source, done automatically when the axis settings change
+    #[func]
+    fn refresh_settings(&mut self) {
+        let settings = Self::get_settings();
+        for eve in self.events_internal.iter_mut() {
+            eve.preference = match &settings {
+                Some(settings) if !eve.settings_key.is_empty() => {
+                    settings.bind().get_preference(&eve.settings_key)
+                }
+                _ => AxisPreference::default(),
+            };
+        }
+    }
+
+    #[func]
+    fn on_axis_settings_changed(&mut self, _key: GString) {
+        self.refresh_settings();
+    }
+
+    fn get_settings() -> Option<Gd<GameAxisSettings>> {
+        Sqore::singleton().bind().get_config().bind().get_axes()
+    }
 }
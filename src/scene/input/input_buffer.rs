@@ -0,0 +1,249 @@
+//! Forgiving input for action gameplay.
+//!
+//! [InputBuffer] remembers recent action presses, so a state can ask whether jump was pressed shortly before landing instead of only on the exact frame. It also watches for [InputCombo] sequences such as down, forward, attack.
+
+use std::collections::VecDeque;
+
+use godot::{
+    engine::{Engine, InputEvent, InputMap, Time},
+    prelude::*,
+};
+
+/// Presses beyond this are dropped even if they are still inside the buffer window
+const MAX_PRESSES: usize = 64;
+
+#[derive(Debug, Clone)]
+struct BufferedPress {
+    action: StringName,
+    time_msec: u64,
+    physics_frame: u64,
+}
+
+/// A sequence of actions that must be pressed in order
+#[derive(GodotClass)]
+#[class(tool, base=Resource)]
+pub struct InputCombo {
+    /// Sent with `InputBuffer.combo_performed`
+    #[export]
+    pub name: StringName,
+    #[export]
+    pub sequence: Array<StringName>,
+    /// Physics frames allowed between the first and last press of the sequence
+    #[export]
+    pub window_frames: i32,
+    /// Presses of other actions in between break the combo unless this is set
+    #[export]
+    pub allow_interruptions: bool,
+    /// Removes the presses of the combo from the buffer, so they don't also trigger single-press actions or other combos
+    #[export]
+    pub consume: bool,
+
+    base: Base<Resource>,
+}
+
+#[godot_api]
+impl IResource for InputCombo {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            name: StringName::default(),
+            sequence: Array::new(),
+            window_frames: 20,
+            allow_interruptions: false,
+            consume: true,
+            base,
+        }
+    }
+}
+
+#[godot_api]
+impl InputCombo {}
+
+/// Records action presses from unhandled input along with when they happened.
+///
+/// States query presses with `was_pressed` and take them with `consume`, so one press only triggers one thing.
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct InputBuffer {
+    /// The actions to record. Every action in the `InputMap` outside of the built-in `ui_` ones is recorded if empty
+    #[export]
+    actions: Array<StringName>,
+    /// Milliseconds a press is kept for
+    #[export]
+    buffer_msec: i64,
+    #[export]
+    combos: Array<Gd<InputCombo>>,
+
+    tracked: Vec<StringName>,
+    presses: VecDeque<BufferedPress>,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for InputBuffer {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            actions: Array::new(),
+            buffer_msec: 500,
+            combos: Array::new(),
+            tracked: Vec::new(),
+            presses: VecDeque::new(),
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        self.refresh_actions();
+    }
+
+    fn unhandled_input(&mut self, event: Gd<InputEvent>) {
+        let pressed: Vec<StringName> = self
+            .tracked
+            .iter()
+            .filter(|action| event.is_action_pressed((*action).clone()))
+            .cloned()
+            .collect();
+        if pressed.is_empty() {
+            return;
+        }
+        let time_msec = Time::singleton().get_ticks_msec();
+        let physics_frame = Engine::singleton().get_physics_frames();
+        for action in pressed {
+            self.presses.push_back(BufferedPress {
+                action,
+                time_msec,
+                physics_frame,
+            });
+        }
+        self.prune();
+        self.check_combos();
+    }
+}
+
+#[godot_api]
+impl InputBuffer {
+    pub const SIGNAL_COMBO_PERFORMED: &'static str = "combo_performed";
+
+    #[signal]
+    fn combo_performed(combo_name: StringName) {}
+
+    /// Picks up changes to `actions` or the `InputMap`
+    #[func]
+    pub fn refresh_actions(&mut self) {
+        self.tracked = if self.actions.is_empty() {
+            InputMap::singleton()
+                .get_actions()
+                .iter_shared()
+                .filter(|action| !action.to_string().starts_with("ui_"))
+                .collect()
+        } else {
+            self.actions.iter_shared().collect()
+        };
+    }
+
+    /// Whether the action was pressed in the last `within_msec` milliseconds, without consuming it
+    #[func]
+    pub fn was_pressed(&self, action: StringName, within_msec: i64) -> bool {
+        self.find_press(&action, within_msec).is_some()
+    }
+
+    /// Like `was_pressed`, but removes the press so later queries don't see it again
+    #[func]
+    pub fn consume(&mut self, action: StringName, within_msec: i64) -> bool {
+        let Some(index) = self.find_press(&action, within_msec) else {
+            return false;
+        };
+        self.presses.remove(index);
+        true
+    }
+
+    /// Milliseconds since the action was last pressed, or -1 if it isn't in the buffer
+    #[func]
+    pub fn get_press_age(&self, action: StringName) -> i64 {
+        let now = Time::singleton().get_ticks_msec();
+        self.presses
+            .iter()
+            .rev()
+            .find(|press| press.action == action)
+            .map_or(-1, |press| now.saturating_sub(press.time_msec) as i64)
+    }
+
+    #[func]
+    pub fn clear(&mut self) {
+        self.presses.clear();
+    }
+
+    fn find_press(&self, action: &StringName, within_msec: i64) -> Option<usize> {
+        let now = Time::singleton().get_ticks_msec();
+        let within_msec = within_msec.clamp(0, self.buffer_msec) as u64;
+        self.presses
+            .iter()
+            .enumerate()
+            .rev()
+            .take_while(|(_, press)| now.saturating_sub(press.time_msec) <= within_msec)
+            .find(|(_, press)| &press.action == action)
+            .map(|(index, _)| index)
+    }
+
+    fn prune(&mut self) {
+        let now = Time::singleton().get_ticks_msec();
+        let buffer_msec = self.buffer_msec.max(0) as u64;
+        while let Some(oldest) = self.presses.front() {
+            if self.presses.len() <= MAX_PRESSES
+                && now.saturating_sub(oldest.time_msec) <= buffer_msec
+            {
+                break;
+            }
+            self.presses.pop_front();
+        }
+    }
+
+    fn check_combos(&mut self) {
+        let combos: Vec<Gd<InputCombo>> = self.combos.iter_shared().collect();
+        for combo in combos {
+            let combo = combo.bind();
+            let Some(matched) = self.match_combo(&combo) else {
+                continue;
+            };
+            if combo.consume {
+                // indices are in descending order, so removing them in turn keeps the rest valid
+                for index in matched {
+                    self.presses.remove(index);
+                }
+            }
+            // deferred, so handlers can query and consume presses
+            self.base_mut().call_deferred(
+                StringName::from("emit_signal"),
+                &[
+                    StringName::from(Self::SIGNAL_COMBO_PERFORMED).to_variant(),
+                    combo.name.to_variant(),
+                ],
+            );
+        }
+    }
+
+    /// The buffer indices of the presses that complete the combo, newest first. The newest press must be the last action of the sequence
+    fn match_combo(&self, combo: &InputCombo) -> Option<Vec<usize>> {
+        let sequence: Vec<StringName> = combo.sequence.iter_shared().collect();
+        let last_frame = self.presses.back()?.physics_frame;
+        let mut remaining = sequence.iter().rev().peekable();
+        let mut matched = Vec::with_capacity(sequence.len());
+        for (index, press) in self.presses.iter().enumerate().rev() {
+            let Some(expected) = remaining.peek() else {
+                break;
+            };
+            if last_frame.saturating_sub(press.physics_frame) > combo.window_frames.max(0) as u64 {
+                return None;
+            }
+            if &press.action == *expected {
+                matched.push(index);
+                remaining.next();
+            } else if !combo.allow_interruptions || matched.is_empty() {
+                return None;
+            }
+        }
+        if sequence.is_empty() || remaining.peek().is_some() {
+            return None;
+        }
+        Some(matched)
+    }
+}
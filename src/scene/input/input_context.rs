@@ -0,0 +1,232 @@
+//! Layers of input, so the same button can mean different things in gameplay, dialog, and menus.
+//!
+//! An [InputContext] names a group of actions. Active contexts form a stack ordered by priority, and consumers ask the [InputContextStack] whether an action is live instead of reading [Input] directly. Going from the highest priority down, an action is live in the first context that lists it, blocked by the first `exclusive` context that doesn't, and live if no context decides either way.
+
+use godot::{
+    engine::{Engine, InputEvent},
+    prelude::*,
+};
+
+use crate::scene::game_globals::Sqore;
+
+pub fn register_singleton() {
+    Engine::singleton().register_singleton(
+        StringName::from(InputContextStack::SINGLETON_NAME),
+        InputContextStack::new_alloc().upcast(),
+    );
+}
+
+pub fn unregister_singleton() {
+    Engine::singleton().unregister_singleton(StringName::from(InputContextStack::SINGLETON_NAME));
+}
+
+#[derive(GodotClass)]
+#[class(tool, base=Resource)]
+pub struct InputContext {
+    #[export]
+    pub name: StringName,
+    /// Higher priorities decide first. Equal priorities are ordered by when they were pushed, latest first
+    #[export]
+    pub priority: i32,
+    /// The actions that are live while this context is active
+    #[export]
+    pub actions: Array<StringName>,
+    /// Blocks every action this context doesn't list from lower contexts, such as gameplay actions while a menu is open
+    #[export]
+    pub exclusive: bool,
+
+    base: Base<Resource>,
+}
+
+#[godot_api]
+impl IResource for InputContext {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            name: StringName::default(),
+            priority: 0,
+            actions: Array::new(),
+            exclusive: false,
+            base,
+        }
+    }
+}
+
+#[godot_api]
+impl InputContext {
+    #[func]
+    pub fn has_action(&self, action: StringName) -> bool {
+        self.actions.contains(&action)
+    }
+}
+
+struct ActiveContext {
+    context: Gd<InputContext>,
+    /// How many times the context was pushed, it stays active until popped as often
+    count: u32,
+    /// Increases with every push, to order contexts of equal priority
+    order: u64,
+}
+
+/// The active input contexts. Named contexts are looked up in `input_contexts` of the Sqore config
+#[derive(GodotClass)]
+#[class(base=Object)]
+pub struct InputContextStack {
+    active: Vec<ActiveContext>,
+    next_order: u64,
+    base: Base<Object>,
+}
+
+#[godot_api]
+impl IObject for InputContextStack {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            active: Vec::new(),
+            next_order: 0,
+            base,
+        }
+    }
+}
+
+#[godot_api]
+impl InputContextStack {
+    pub const SINGLETON_NAME: &'static str = "InputContextStack";
+    pub const SIGNAL_CONTEXTS_CHANGED: &'static str = "contexts_changed";
+
+    #[signal]
+    fn contexts_changed() {}
+
+    /// Activates the context with the given name from the Sqore config. Returns false if there is none
+    #[func]
+    pub fn push_context(&mut self, name: StringName) -> bool {
+        let Some(context) = Self::find_configured(&name) else {
+            godot_warn!("No input context named \"{}\" in the Sqore config", name);
+            return false;
+        };
+        self.push_context_resource(context);
+        true
+    }
+
+    /// Activates a context that isn't in the config. Contexts are identified by name, so pushing a second context with the same name only counts as another push of the first one
+    #[func]
+    pub fn push_context_resource(&mut self, context: Gd<InputContext>) {
+        let name = context.bind().name.clone();
+        if let Some(active) = self.find_active_mut(&name) {
+            active.count += 1;
+            return;
+        }
+        self.active.push(ActiveContext {
+            context,
+            count: 1,
+            order: self.next_order,
+        });
+        self.next_order += 1;
+        // highest priority first, and the latest push first among equals
+        self.active.sort_by(|a, b| {
+            let priority = |active: &ActiveContext| active.context.bind().priority;
+            priority(b).cmp(&priority(a)).then(b.order.cmp(&a.order))
+        });
+        self.emit_changed();
+    }
+
+    /// Undoes one push of the context
+    #[func]
+    pub fn pop_context(&mut self, name: StringName) {
+        let Some(active) = self.find_active_mut(&name) else {
+            return;
+        };
+        active.count -= 1;
+        if active.count == 0 {
+            self.active
+                .retain(|active| active.context.bind().name != name);
+            self.emit_changed();
+        }
+    }
+
+    #[func]
+    pub fn is_context_active(&self, name: StringName) -> bool {
+        self.active
+            .iter()
+            .any(|active| active.context.bind().name == name)
+    }
+
+    /// Names of the active contexts, highest priority first
+    #[func]
+    pub fn get_active_contexts(&self) -> Array<StringName> {
+        self.active
+            .iter()
+            .map(|active| active.context.bind().name.clone())
+            .collect()
+    }
+
+    /// Whether the action should be handled at all right now
+    #[func]
+    pub fn is_action_live(&self, action: StringName) -> bool {
+        for active in self.active.iter() {
+            let context = active.context.bind();
+            if context.has_action(action.clone()) {
+                return true;
+            }
+            if context.exclusive {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// `Input.is_action_pressed`, but only for live actions
+    #[func]
+    pub fn is_action_pressed(&self, action: StringName) -> bool {
+        self.is_action_live(action.clone()) && Input::singleton().is_action_pressed(action)
+    }
+
+    /// `Input.is_action_just_pressed`, but only for live actions
+    #[func]
+    pub fn is_action_just_pressed(&self, action: StringName) -> bool {
+        self.is_action_live(action.clone()) && Input::singleton().is_action_just_pressed(action)
+    }
+
+    /// `InputEvent.is_action_pressed`, but only for live actions. For `_input` and `_unhandled_input` handlers
+    #[func]
+    pub fn is_event_action_pressed(&self, event: Gd<InputEvent>, action: StringName) -> bool {
+        self.is_action_live(action.clone()) && event.is_action_pressed(action)
+    }
+
+    fn find_active_mut(&mut self, name: &StringName) -> Option<&mut ActiveContext> {
+        self.active
+            .iter_mut()
+            .find(|active| active.context.bind().name == *name)
+    }
+
+    /// The context with the given name in the Sqore config
+    pub fn find_configured(name: &StringName) -> Option<Gd<InputContext>> {
+        Sqore::singleton()
+            .bind()
+            .get_config()
+            .bind()
+            .input_contexts
+            .iter_shared()
+            .find(|context| context.bind().name == *name)
+    }
+
+    fn emit_changed(&mut self) {
+        // deferred, so handlers can push and pop contexts themselves
+        self.base_mut().call_deferred(
+            StringName::from("emit_signal"),
+            &[StringName::from(Self::SIGNAL_CONTEXTS_CHANGED).to_variant()],
+        );
+    }
+
+    pub fn singleton() -> Gd<InputContextStack> {
+        let Some(vol) = Engine::singleton().get_singleton(StringName::from(Self::SINGLETON_NAME))
+        else {
+            panic!("Failed to find engine singleton for InputContextStack. You must access this after it is registered!");
+        };
+        let res_core: Result<Gd<InputContextStack>, Gd<_>> = vol.try_cast();
+        let Ok(core) = res_core else {
+            panic!(
+                "Failed to cast engine singleton for InputContextStack. This should never happen!"
+            );
+        };
+        core
+    }
+}
@@ -0,0 +1,215 @@
+//! Which kind of device the player last used, so prompts, focus, and mouse handling can follow along.
+//!
+//! [InputDeviceMonitor] is an engine singleton. Since singletons are separate from the scene tree, it adds a small [InputDeviceWatcher] node to the root that forwards input events to it.
+
+use godot::{
+    engine::{
+        node::ProcessMode, Engine, InputEvent, InputEventJoypadButton, InputEventJoypadMotion,
+        InputEventKey, InputEventMouseButton, InputEventMouseMotion, InputEventScreenTouch,
+    },
+    prelude::*,
+};
+
+use super::input_labels::JoypadStyle;
+use crate::{scene::game_globals::Sqore, util::SqoreUtil};
+
+const S_DEAD_ZONE: &str = "input/device_dead_zone";
+
+pub fn register_singleton() {
+    Engine::singleton().register_singleton(
+        StringName::from(InputDeviceMonitor::SINGLETON_NAME),
+        InputDeviceMonitor::new_alloc().upcast(),
+    );
+}
+
+pub fn unregister_singleton() {
+    Engine::singleton().unregister_singleton(StringName::from(InputDeviceMonitor::SINGLETON_NAME));
+}
+
+#[derive(GodotConvert, Var, Export, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[godot(via=i32)]
+pub enum InputDeviceClass {
+    #[default]
+    KeyboardMouse = 0,
+    Joypad = 1,
+}
+
+impl InputDeviceClass {
+    /// Whether the event comes from this kind of device
+    pub fn owns_event(&self, event: &Gd<InputEvent>) -> bool {
+        match self {
+            Self::KeyboardMouse => {
+                event.clone().try_cast::<InputEventKey>().is_ok()
+                    || event.clone().try_cast::<InputEventMouseButton>().is_ok()
+            }
+            Self::Joypad => {
+                event.clone().try_cast::<InputEventJoypadButton>().is_ok()
+                    || event.clone().try_cast::<InputEventJoypadMotion>().is_ok()
+            }
+        }
+    }
+}
+
+/// Tracks the last used input device. Watching starts the first time the device is queried, or when `start_watching` is called, which should be done before connecting to `input_device_changed`.
+///
+/// Joystick movement within `dead_zone` (the `addons/sqore/input/device_dead_zone` project setting) is ignored, so a drifting stick doesn't take over from the keyboard.
+#[derive(GodotClass)]
+#[class(base=Object)]
+pub struct InputDeviceMonitor {
+    device: InputDeviceClass,
+    /// The vendor layout of the last used joypad. Kept while on keyboard and mouse, so switching back shows the same joypad prompts
+    joypad_style: JoypadStyle,
+    /// The device id of the last used joypad, or -1 if none was used yet
+    #[var(get)]
+    joypad_device: i32,
+    #[var]
+    dead_zone: f32,
+    watcher: Option<Gd<InputDeviceWatcher>>,
+    base: Base<Object>,
+}
+
+#[godot_api]
+impl IObject for InputDeviceMonitor {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            device: InputDeviceClass::KeyboardMouse,
+            joypad_style: JoypadStyle::Generic,
+            joypad_device: -1,
+            dead_zone: Sqore::get_or_init_default(S_DEAD_ZONE, 0.5f32).unwrap_or(0.5),
+            watcher: None,
+            base,
+        }
+    }
+}
+
+#[godot_api]
+impl InputDeviceMonitor {
+    pub const SINGLETON_NAME: &'static str = "InputDeviceMonitor";
+    pub const SIGNAL_INPUT_DEVICE_CHANGED: &'static str = "input_device_changed";
+
+    /// Emitted when the player switches between keyboard and mouse and a joypad, or to a joypad of another vendor
+    #[signal]
+    fn input_device_changed(device: InputDeviceClass, joypad_style: JoypadStyle) {}
+
+    /// Adds the node that watches input events to the scene tree, if it isn't there yet
+    #[func]
+    pub fn start_watching(&mut self) {
+        if self.watcher.is_some() || Engine::singleton().is_editor_hint() {
+            return;
+        }
+        let Some(tree) = SqoreUtil::get_scene_tree_global() else {
+            return;
+        };
+        let Some(mut root) = tree.get_root() else {
+            return;
+        };
+        // until a joypad is used, guess its vendor from whichever is connected
+        if self.joypad_device < 0 {
+            self.joypad_style = JoypadStyle::detect();
+        }
+        let mut watcher = InputDeviceWatcher::new_alloc();
+        watcher.set_process_mode(ProcessMode::ALWAYS);
+        self.watcher = Some(watcher.clone());
+        SqoreUtil::add_child_deferred(&mut root.upcast(), &watcher.upcast());
+    }
+
+    #[func]
+    pub fn get_device(&mut self) -> InputDeviceClass {
+        self.start_watching();
+        self.device
+    }
+
+    #[func]
+    pub fn get_joypad_style(&mut self) -> JoypadStyle {
+        self.start_watching();
+        self.joypad_style
+    }
+
+    #[func]
+    pub fn is_using_joypad(&mut self) -> bool {
+        self.get_device() == InputDeviceClass::Joypad
+    }
+
+    /// Updates the active device from the event. Returns whether it changed, in which case the caller emits `input_device_changed` once this is no longer bound
+    pub fn note_event(&mut self, event: &Gd<InputEvent>) -> bool {
+        let previous = (self.device, self.joypad_style);
+        if Self::is_keyboard_mouse_event(event) {
+            self.device = InputDeviceClass::KeyboardMouse;
+        } else if self.is_joypad_event(event) {
+            self.device = InputDeviceClass::Joypad;
+            if event.get_device() != self.joypad_device {
+                self.joypad_device = event.get_device();
+                self.joypad_style = JoypadStyle::for_device(self.joypad_device);
+            }
+        }
+        previous != (self.device, self.joypad_style)
+    }
+
+    fn is_keyboard_mouse_event(event: &Gd<InputEvent>) -> bool {
+        if let Ok(motion) = event.clone().try_cast::<InputEventMouseMotion>() {
+            // touch screens emulate mouse motion, and a bumped desk shouldn't count either
+            return motion.get_relative().length() > 1.0;
+        }
+        event.clone().try_cast::<InputEventKey>().is_ok()
+            || event.clone().try_cast::<InputEventMouseButton>().is_ok()
+            || event.clone().try_cast::<InputEventScreenTouch>().is_ok()
+    }
+
+    fn is_joypad_event(&self, event: &Gd<InputEvent>) -> bool {
+        if let Ok(motion) = event.clone().try_cast::<InputEventJoypadMotion>() {
+            return motion.get_axis_value().abs() > self.dead_zone;
+        }
+        event.clone().try_cast::<InputEventJoypadButton>().is_ok()
+    }
+
+    pub fn singleton() -> Gd<InputDeviceMonitor> {
+        let Some(vol) = Engine::singleton().get_singleton(StringName::from(Self::SINGLETON_NAME))
+        else {
+            panic!("Failed to find engine singleton for InputDeviceMonitor. You must access this after it is registered!");
+        };
+        let res_core: Result<Gd<InputDeviceMonitor>, Gd<_>> = vol.try_cast();
+        let Ok(core) = res_core else {
+            panic!(
+                "Failed to cast engine singleton for InputDeviceMonitor. This should never happen!"
+            );
+        };
+        core
+    }
+}
+
+/// Forwards input events to the [InputDeviceMonitor]. Added to the root automatically
+#[derive(GodotClass)]
+#[class(init, base=Node)]
+pub struct InputDeviceWatcher {
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for InputDeviceWatcher {
+    fn input(&mut self, event: Gd<InputEvent>) {
+        let mut monitor = InputDeviceMonitor::singleton();
+        if !monitor.bind_mut().note_event(&event) {
+            return;
+        }
+        let (device, style) = {
+            let bind = monitor.bind();
+            (bind.device, bind.joypad_style)
+        };
+        monitor.emit_signal(
+            StringName::from(InputDeviceMonitor::SIGNAL_INPUT_DEVICE_CHANGED),
+            &[device.to_variant(), style.to_variant()],
+        );
+    }
+
+    fn exit_tree(&mut self) {
+        // lets the monitor add a new watcher if the root is ever replaced
+        InputDeviceMonitor::singleton().bind_mut().watcher = None;
+    }
+}
+
+/// The last used device, and the style of the last used joypad
+pub fn get_active_device() -> (InputDeviceClass, JoypadStyle) {
+    let mut monitor = InputDeviceMonitor::singleton();
+    let mut bind = monitor.bind_mut();
+    (bind.get_device(), bind.get_joypad_style())
+}
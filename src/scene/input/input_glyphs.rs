@@ -0,0 +1,219 @@
+//! Prompt icons for inputs.
+//!
+//! Text can refer to an action with `[action=interact]`, which is replaced with the glyph of the input currently bound to it in the [InputMap] for the given device. Callers usually pass the last used device from [InputDeviceMonitor](super::input_device::InputDeviceMonitor). Inputs without a glyph are written out by name instead, such as "E" or "Cross".
+
+use godot::{
+    engine::{
+        InputEvent, InputEventJoypadButton, InputEventJoypadMotion, InputEventKey,
+        InputEventMouseButton, InputMap, Os, Texture2D,
+    },
+    prelude::*,
+};
+
+use super::{
+    input_device::InputDeviceClass,
+    input_labels::{self, JoypadStyle},
+};
+use crate::scene::game_globals::Sqore;
+
+const TAG_ACTION: &str = "action";
+
+/// Glyphs for one joypad layout
+#[derive(GodotClass)]
+#[class(tool, init, base=Resource)]
+pub struct JoypadGlyphs {
+    /// `JoyButton` index to texture
+    #[export]
+    pub buttons: Dictionary,
+    /// Axis index followed by the direction to texture, such as "1-" for pushing the left stick up or "4+" for the left trigger
+    #[export]
+    pub axes: Dictionary,
+    base: Base<Resource>,
+}
+
+#[godot_api]
+impl JoypadGlyphs {}
+
+/// Textures for keys, mouse buttons, and the buttons of each joypad layout. Set it as `input_glyphs` in the [SqoreConfig](crate::scene::game_settings::SqoreConfig) to use it for `[action=name]` tags.
+#[derive(GodotClass)]
+#[class(tool, base=Resource)]
+pub struct InputGlyphs {
+    /// Key name (as given by `OS.get_keycode_string`, such as "E" or "Space") to texture
+    #[export]
+    pub keys: Dictionary,
+    /// `MouseButton` index to texture
+    #[export]
+    pub mouse_buttons: Dictionary,
+    /// Used for joypads that aren't recognized, and for layouts that have no glyphs of their own
+    #[export]
+    pub generic: Option<Gd<JoypadGlyphs>>,
+    #[export]
+    pub xbox: Option<Gd<JoypadGlyphs>>,
+    #[export]
+    pub playstation: Option<Gd<JoypadGlyphs>>,
+    #[export]
+    pub nintendo: Option<Gd<JoypadGlyphs>>,
+    /// Height of glyphs in BBCode text, in pixels
+    #[export]
+    pub glyph_height: i32,
+    base: Base<Resource>,
+}
+
+#[godot_api]
+impl IResource for InputGlyphs {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            keys: Dictionary::new(),
+            mouse_buttons: Dictionary::new(),
+            generic: None,
+            xbox: None,
+            playstation: None,
+            nintendo: None,
+            glyph_height: 32,
+            base,
+        }
+    }
+}
+
+#[godot_api]
+impl InputGlyphs {
+    /// The glyph for the event, if there is one
+    #[func]
+    pub fn get_event_glyph(
+        &self,
+        event: Gd<InputEvent>,
+        style: JoypadStyle,
+    ) -> Option<Gd<Texture2D>> {
+        let glyph = if let Ok(key) = event.clone().try_cast::<InputEventKey>() {
+            let name = Os::singleton().get_keycode_string(input_labels::get_layout_keycode(&key));
+            self.keys.get(name)
+        } else if let Ok(button) = event.clone().try_cast::<InputEventMouseButton>() {
+            self.mouse_buttons.get(button.get_button_index().ord())
+        } else if let Ok(button) = event.clone().try_cast::<InputEventJoypadButton>() {
+            let index = button.get_button_index().ord();
+            self.find_joypad_glyph(style, |glyphs| glyphs.buttons.get(index))
+        } else if let Ok(motion) = event.try_cast::<InputEventJoypadMotion>() {
+            let direction = if motion.get_axis_value() < 0.0 {
+                "-"
+            } else {
+                "+"
+            };
+            let key = format!("{}{}", motion.get_axis().ord(), direction);
+            self.find_joypad_glyph(style, |glyphs| glyphs.axes.get(key.as_str()))
+        } else {
+            None
+        };
+        glyph.and_then(|glyph| Gd::<Texture2D>::try_from_variant(&glyph).ok())
+    }
+
+    /// The glyph of the input bound to the action for the device, such as the one from `InputDeviceMonitor.get_device`
+    #[func]
+    pub fn get_action_glyph(
+        &self,
+        action: StringName,
+        device: InputDeviceClass,
+        style: JoypadStyle,
+    ) -> Option<Gd<Texture2D>> {
+        let event = get_action_event(action, device)?;
+        self.get_event_glyph(event, style)
+    }
+
+    /// Replaces `[action=name]` tags with BBCode images, or the input names where there is no glyph
+    #[func]
+    pub fn format_bbcode(text: GString, device: InputDeviceClass, style: JoypadStyle) -> GString {
+        expand_action_tags(&text.to_string(), true, device, style).to_godot()
+    }
+
+    /// Replaces `[action=name]` tags with the input names, for text that can't show images (such as a [Label](godot::engine::Label))
+    #[func]
+    pub fn format_plain(text: GString, device: InputDeviceClass, style: JoypadStyle) -> GString {
+        expand_action_tags(&text.to_string(), false, device, style).to_godot()
+    }
+
+    fn find_joypad_glyph(
+        &self,
+        style: JoypadStyle,
+        lookup: impl Fn(&JoypadGlyphs) -> Option<Variant>,
+    ) -> Option<Variant> {
+        let layout = match style {
+            JoypadStyle::Generic => None,
+            JoypadStyle::Xbox => self.xbox.as_ref(),
+            JoypadStyle::PlayStation => self.playstation.as_ref(),
+            JoypadStyle::Nintendo => self.nintendo.as_ref(),
+        };
+        layout
+            .and_then(|glyphs| lookup(&*glyphs.bind()))
+            .or_else(|| lookup(&*self.generic.as_ref()?.bind()))
+    }
+}
+
+/// The first event bound to the action that comes from the device, or any bound event if the device has none
+pub fn get_action_event(action: StringName, device: InputDeviceClass) -> Option<Gd<InputEvent>> {
+    let mut map = InputMap::singleton();
+    if !map.has_action(action.clone()) {
+        return None;
+    }
+    let events = map.action_get_events(action);
+    events
+        .iter_shared()
+        .find(|event| device.owns_event(event))
+        .or_else(|| events.iter_shared().next())
+}
+
+/// Replaces every `[action=name]` tag in the text with the input for the device. With `allow_images`, inputs that have a glyph become an `[img]` tag
+pub fn expand_action_tags(
+    text: &str,
+    allow_images: bool,
+    device: InputDeviceClass,
+    style: JoypadStyle,
+) -> String {
+    let prefix = format!("[{}=", TAG_ACTION);
+    if !text.contains(&prefix) {
+        return text.to_string();
+    }
+    let glyphs = Sqore::singleton()
+        .bind()
+        .get_config()
+        .bind()
+        .input_glyphs
+        .clone();
+    let mut buffer = String::with_capacity(text.len());
+    let mut remaining = text;
+    while let Some(open) = remaining.find(&prefix) {
+        let (pre, post) = remaining.split_at(open);
+        buffer += pre;
+        let Some(close) = post.find(']') else {
+            remaining = post;
+            break;
+        };
+        let action = post[prefix.len()..close].trim();
+        remaining = &post[close + 1..];
+        let Some(event) = get_action_event(action.into(), device) else {
+            godot_warn!(
+                "Cannot show input for unknown or unbound action \"{}\"",
+                action
+            );
+            buffer += action;
+            continue;
+        };
+        let image = glyphs
+            .as_ref()
+            .filter(|_| allow_images)
+            .and_then(|glyphs| {
+                let bind = glyphs.bind();
+                Some((
+                    bind.get_event_glyph(event.clone(), style)?,
+                    bind.glyph_height,
+                ))
+            })
+            .filter(|(texture, _)| !texture.get_path().is_empty());
+        match image {
+            Some((texture, height)) => {
+                buffer += &format!("[img height={}]{}[/img]", height, texture.get_path());
+            }
+            None => buffer += &input_labels::get_event_label(&event, style),
+        }
+    }
+    buffer += remaining;
+    buffer
+}
@@ -0,0 +1,162 @@
+//! Readable names for bound inputs, such as "Space", "Right Mouse", or "Cross".
+//!
+//! Joypad buttons are named after the layout of the connected controller, since the same button is "A" on an Xbox controller, "Cross" on a PlayStation controller, and "B" on a Nintendo controller.
+
+use godot::{
+    engine::{
+        global::Key, DisplayServer, InputEvent, InputEventJoypadButton, InputEventJoypadMotion,
+        InputEventKey, InputEventMouseButton, Os,
+    },
+    prelude::*,
+};
+
+#[derive(GodotConvert, Var, Export, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[godot(via=i32)]
+pub enum JoypadStyle {
+    /// Unknown controllers use Xbox names, which is the layout SDL maps every controller to
+    #[default]
+    Generic = 0,
+    Xbox = 1,
+    PlayStation = 2,
+    Nintendo = 3,
+}
+
+impl JoypadStyle {
+    pub fn from_joy_name(name: &str) -> Self {
+        let name = name.to_lowercase();
+        let matches = |patterns: &[&str]| patterns.iter().any(|pattern| name.contains(pattern));
+        if matches(&["xbox", "xinput"]) {
+            Self::Xbox
+        } else if matches(&[
+            "playstation",
+            "ps3",
+            "ps4",
+            "ps5",
+            "dualshock",
+            "dualsense",
+            "sony",
+        ]) {
+            Self::PlayStation
+        } else if matches(&["nintendo", "switch", "joy-con", "joycon", "pro controller"]) {
+            Self::Nintendo
+        } else {
+            Self::Generic
+        }
+    }
+
+    /// The style of the joypad with the given device id
+    pub fn for_device(device: i32) -> Self {
+        let name = Input::singleton().get_joy_name(device);
+        Self::from_joy_name(&name.to_string())
+    }
+
+    /// The style of the first connected joypad
+    pub fn detect() -> Self {
+        let joypads = Input::singleton().get_connected_joypads();
+        match joypads.iter_shared().next() {
+            Some(device) => Self::for_device(device as i32),
+            None => Self::Generic,
+        }
+    }
+}
+
+/// The name of the input that triggers the event. Events that can't be bound fall back to `InputEvent.as_text`
+pub fn get_event_label(event: &Gd<InputEvent>, style: JoypadStyle) -> String {
+    if let Ok(key) = event.clone().try_cast::<InputEventKey>() {
+        return get_key_label(&key);
+    }
+    if let Ok(button) = event.clone().try_cast::<InputEventMouseButton>() {
+        return get_mouse_button_label(button.get_button_index().ord());
+    }
+    if let Ok(button) = event.clone().try_cast::<InputEventJoypadButton>() {
+        return get_joy_button_label(button.get_button_index().ord(), style);
+    }
+    if let Ok(motion) = event.clone().try_cast::<InputEventJoypadMotion>() {
+        return get_joy_axis_label(motion.get_axis().ord(), motion.get_axis_value(), style);
+    }
+    event.as_text().to_string()
+}
+
+/// The keycode of the key on the player's keyboard layout, since physical keys should be named after what they produce
+pub fn get_layout_keycode(key: &Gd<InputEventKey>) -> Key {
+    let physical = key.get_physical_keycode();
+    if physical != Key::NONE {
+        DisplayServer::singleton().keyboard_get_keycode_from_physical(physical)
+    } else {
+        key.get_keycode()
+    }
+}
+
+fn get_key_label(key: &Gd<InputEventKey>) -> String {
+    let keycode = get_layout_keycode(key);
+    let mut label = String::new();
+    for (pressed, name) in [
+        (key.is_ctrl_pressed(), "Ctrl+"),
+        (key.is_shift_pressed(), "Shift+"),
+        (key.is_alt_pressed(), "Alt+"),
+        (key.is_meta_pressed(), "Meta+"),
+    ] {
+        if pressed {
+            label += name;
+        }
+    }
+    label += &Os::singleton().get_keycode_string(keycode).to_string();
+    label
+}
+
+fn get_mouse_button_label(index: i32) -> String {
+    match index {
+        1 => "Left Mouse".into(),
+        2 => "Right Mouse".into(),
+        3 => "Middle Mouse".into(),
+        4 => "Wheel Up".into(),
+        5 => "Wheel Down".into(),
+        6 => "Wheel Left".into(),
+        7 => "Wheel Right".into(),
+        8 => "Mouse Back".into(),
+        9 => "Mouse Forward".into(),
+        _ => format!("Mouse {}", index),
+    }
+}
+
+fn get_joy_button_label(index: i32, style: JoypadStyle) -> String {
+    // face buttons, back, guide, start, sticks, and shoulders in `JoyButton` order
+    let names: [&str; 11] = match style {
+        JoypadStyle::Generic | JoypadStyle::Xbox => [
+            "A", "B", "X", "Y", "View", "Xbox", "Menu", "LS", "RS", "LB", "RB",
+        ],
+        JoypadStyle::PlayStation => [
+            "Cross", "Circle", "Square", "Triangle", "Share", "PS", "Options", "L3", "R3", "L1",
+            "R1",
+        ],
+        JoypadStyle::Nintendo => [
+            "B", "A", "Y", "X", "-", "Home", "+", "L Stick", "R Stick", "L", "R",
+        ],
+    };
+    match index {
+        0..=10 => names[index as usize].into(),
+        11 => "D-Pad Up".into(),
+        12 => "D-Pad Down".into(),
+        13 => "D-Pad Left".into(),
+        14 => "D-Pad Right".into(),
+        _ => format!("Button {}", index),
+    }
+}
+
+fn get_joy_axis_label(axis: i32, value: f32, style: JoypadStyle) -> String {
+    let (left_trigger, right_trigger) = match style {
+        JoypadStyle::Generic | JoypadStyle::Xbox => ("LT", "RT"),
+        JoypadStyle::PlayStation => ("L2", "R2"),
+        JoypadStyle::Nintendo => ("ZL", "ZR"),
+    };
+    let negative = value < 0.0;
+    match axis {
+        0 => format!("Left Stick {}", if negative { "Left" } else { "Right" }),
+        1 => format!("Left Stick {}", if negative { "Up" } else { "Down" }),
+        2 => format!("Right Stick {}", if negative { "Left" } else { "Right" }),
+        3 => format!("Right Stick {}", if negative { "Up" } else { "Down" }),
+        4 => left_trigger.into(),
+        5 => right_trigger.into(),
+        _ => format!("Axis {}{}", axis, if negative { "-" } else { "+" }),
+    }
+}
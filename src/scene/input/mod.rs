@@ -1 +1,9 @@
+pub mod haptics_manager;
 pub mod input_axis_allocator;
+pub mod input_buffer;
+pub mod input_context;
+pub mod input_device;
+pub mod input_glyphs;
+pub mod input_labels;
+pub mod mouse_capture;
+pub mod rebind_listener;
@@ -0,0 +1,260 @@
+//! Shared ownership of `Input.mouse_mode`.
+//!
+//! Instead of setting the mouse mode directly, systems push the mode they want onto the [MouseCaptureStack] under a key and pop it when done. The most recent request wins, so closing a pause menu on top of gameplay returns to the captured mouse without either one knowing about the other.
+
+use godot::{
+    engine::{input::MouseMode, Engine},
+    prelude::*,
+};
+
+use crate::util::SqoreUtil;
+
+pub fn register_singleton() {
+    Engine::singleton().register_singleton(
+        StringName::from(MouseCaptureStack::SINGLETON_NAME),
+        MouseCaptureStack::new_alloc().upcast(),
+    );
+}
+
+pub fn unregister_singleton() {
+    Engine::singleton().unregister_singleton(StringName::from(MouseCaptureStack::SINGLETON_NAME));
+}
+
+/// Keeps a stack of requested mouse modes and applies the top one. The mouse is released while the window is unfocused and the requested mode is restored once focus returns.
+#[derive(GodotClass)]
+#[class(base=Object)]
+pub struct MouseCaptureStack {
+    requests: Vec<(StringName, MouseMode)>,
+    /// The mode from before the first request, applied again once the stack is empty
+    base_mode: MouseMode,
+    has_focus: bool,
+    is_watching_focus: bool,
+    base: Base<Object>,
+}
+
+#[godot_api]
+impl IObject for MouseCaptureStack {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            requests: Vec::new(),
+            base_mode: MouseMode::VISIBLE,
+            has_focus: true,
+            is_watching_focus: false,
+            base,
+        }
+    }
+}
+
+#[godot_api]
+impl MouseCaptureStack {
+    pub const SINGLETON_NAME: &'static str = "MouseCaptureStack";
+    pub const SIGNAL_MOUSE_MODE_CHANGED: &'static str = "mouse_mode_changed";
+
+    /// Emitted when the applied mode changes, including the release on focus loss. `mode` is an `Input.MouseMode`
+    #[signal]
+    fn mouse_mode_changed(mode: i32) {}
+
+    /// Requests the mode (an `Input.MouseMode`) under the key. Pushing a key that is already on the stack moves it to the top with the new mode
+    #[func]
+    pub fn push_mode(&mut self, key: StringName, mode: i32) {
+        let Some(mode) = MouseMode::try_from_ord(mode) else {
+            godot_warn!("Cannot push unknown mouse mode {} for \"{}\"", mode, key);
+            return;
+        };
+        self.watch_focus();
+        if self.requests.is_empty() {
+            self.base_mode = Input::singleton().get_mouse_mode();
+        }
+        self.requests.retain(|(other, _)| *other != key);
+        self.requests.push((key, mode));
+        self.apply();
+    }
+
+    /// Removes the request of the key, wherever it is in the stack
+    #[func]
+    pub fn pop_mode(&mut self, key: StringName) {
+        let count = self.requests.len();
+        self.requests.retain(|(other, _)| *other != key);
+        if self.requests.len() != count {
+            self.apply();
+        }
+    }
+
+    #[func]
+    pub fn has_request(&self, key: StringName) -> bool {
+        self.requests.iter().any(|(other, _)| *other == key)
+    }
+
+    /// The mode the stack asks for, even while it is released for focus loss
+    #[func]
+    pub fn get_requested_mode(&self) -> i32 {
+        self.get_requested().ord()
+    }
+
+    #[func]
+    pub fn is_captured(&self) -> bool {
+        self.has_focus && self.get_requested() == MouseMode::CAPTURED
+    }
+
+    /// Drops every request and returns to the mode from before the first one
+    #[func]
+    pub fn clear(&mut self) {
+        self.requests.clear();
+        self.apply();
+    }
+
+    #[func]
+    fn on_focus_entered(&mut self) {
+        self.has_focus = true;
+        self.apply();
+    }
+
+    #[func]
+    fn on_focus_exited(&mut self) {
+        self.has_focus = false;
+        self.apply();
+    }
+
+    fn get_requested(&self) -> MouseMode {
+        self.requests
+            .last()
+            .map_or(self.base_mode, |(_, mode)| *mode)
+    }
+
+    fn apply(&mut self) {
+        let requested = self.get_requested();
+        // a captured or confined mouse can't leave an unfocused window, which makes alt-tabbing painful
+        let mode = if self.has_focus {
+            requested
+        } else if requested == MouseMode::CAPTURED || requested == MouseMode::CONFINED {
+            MouseMode::VISIBLE
+        } else if requested == MouseMode::CONFINED_HIDDEN {
+            MouseMode::HIDDEN
+        } else {
+            requested
+        };
+        let mut input = Input::singleton();
+        if input.get_mouse_mode() == mode {
+            return;
+        }
+        input.set_mouse_mode(mode);
+        // deferred, so handlers may push and pop modes themselves
+        self.base_mut().call_deferred(
+            StringName::from("emit_signal"),
+            &[
+                StringName::from(Self::SIGNAL_MOUSE_MODE_CHANGED).to_variant(),
+                mode.ord().to_variant(),
+            ],
+        );
+    }
+
+    fn watch_focus(&mut self) {
+        if self.is_watching_focus || Engine::singleton().is_editor_hint() {
+            return;
+        }
+        let Some(tree) = SqoreUtil::get_scene_tree_global() else {
+            return;
+        };
+        let Some(mut root) = tree.get_root() else {
+            return;
+        };
+        for (signal, method) in [
+            ("focus_entered", "on_focus_entered"),
+            ("focus_exited", "on_focus_exited"),
+        ] {
+            root.connect(
+                StringName::from(signal),
+                Callable::from_object_method(&self.to_gd(), method),
+            );
+        }
+        self.has_focus = root.has_focus();
+        self.is_watching_focus = true;
+    }
+
+    pub fn singleton() -> Gd<MouseCaptureStack> {
+        let Some(vol) = Engine::singleton().get_singleton(StringName::from(Self::SINGLETON_NAME))
+        else {
+            panic!("Failed to find engine singleton for MouseCaptureStack. You must access this after it is registered!");
+        };
+        let res_core: Result<Gd<MouseCaptureStack>, Gd<_>> = vol.try_cast();
+        let Ok(core) = res_core else {
+            panic!(
+                "Failed to cast engine singleton for MouseCaptureStack. This should never happen!"
+            );
+        };
+        core
+    }
+}
+
+/// Requests a mouse mode from the [MouseCaptureStack] while this node is in the tree, and while its parent is visible for controls and other canvas items. Add one to a pause menu with a visible mouse, or to a player with a captured one.
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct MouseModeRequest {
+    #[export(enum=(Visible=0, Hidden=1, Captured=2, Confined=3, ConfinedHidden=4))]
+    mode: i32,
+    /// Only request the mode while the parent is visible, if it is a CanvasItem
+    #[export]
+    follow_visibility: bool,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for MouseModeRequest {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            mode: MouseMode::VISIBLE.ord(),
+            follow_visibility: true,
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        if let Some(mut parent) = self.get_canvas_parent() {
+            parent.connect(
+                StringName::from("visibility_changed"),
+                Callable::from_object_method(&self.to_gd(), "refresh"),
+            );
+        }
+        self.refresh();
+    }
+
+    fn enter_tree(&mut self) {
+        // the first request is made once ready, this only covers nodes moved back into the tree
+        if self.base().is_node_ready() {
+            self.refresh();
+        }
+    }
+
+    fn exit_tree(&mut self) {
+        MouseCaptureStack::singleton()
+            .bind_mut()
+            .pop_mode(self.get_key());
+    }
+}
+
+#[godot_api]
+impl MouseModeRequest {
+    /// Pushes or pops the request to match the visibility of the parent, also after changing `mode`
+    #[func]
+    pub fn refresh(&mut self) {
+        let is_active = match self.get_canvas_parent() {
+            Some(parent) if self.follow_visibility => parent.is_visible_in_tree(),
+            _ => true,
+        };
+        let key = self.get_key();
+        let mut stack = MouseCaptureStack::singleton();
+        if is_active {
+            stack.bind_mut().push_mode(key, self.mode);
+        } else {
+            stack.bind_mut().pop_mode(key);
+        }
+    }
+
+    fn get_canvas_parent(&self) -> Option<Gd<CanvasItem>> {
+        self.base().get_parent()?.try_cast::<CanvasItem>().ok()
+    }
+
+    fn get_key(&self) -> StringName {
+        StringName::from(format!("MouseModeRequest_{}", self.base().instance_id()).as_str())
+    }
+}
@@ -0,0 +1,121 @@
+use godot::{
+    engine::{
+        global::Key, InputEvent, InputEventJoypadButton, InputEventJoypadMotion, InputEventKey,
+        InputEventMouseButton,
+    },
+    prelude::*,
+};
+
+use crate::scene::game_settings::controls::GameControlsSettings;
+
+/// The device id of events that match input from every device
+pub const DEVICE_ALL: i32 = -1;
+
+/// Waits for the next bindable input and hands it to [GameControlsSettings]. Created by `GameControlsSettings.start_rebind`, and frees itself once done. Pressing escape cancels.
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct RebindListener {
+    pub settings: Option<Gd<GameControlsSettings>>,
+    pub action: GString,
+    pub slot: i32,
+    /// How far a joystick axis has to be pushed before it counts as input
+    #[var]
+    pub axis_threshold: f32,
+    /// Set once an input was captured, so events arriving before the deferred finish are ignored
+    done: bool,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for RebindListener {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            settings: None,
+            action: GString::new(),
+            slot: 0,
+            axis_threshold: 0.5,
+            done: false,
+            base,
+        }
+    }
+
+    fn input(&mut self, event: Gd<InputEvent>) {
+        if self.done {
+            return;
+        }
+        let Some(captured) = self.capture(event) else {
+            return;
+        };
+        self.done = true;
+        if let Some(viewport) = &mut self.base().get_viewport() {
+            viewport.set_input_as_handled();
+        }
+        let Some(mut settings) = self.settings.clone() else {
+            self.base_mut().queue_free();
+            return;
+        };
+        let args = [
+            self.action.to_variant(),
+            self.slot.to_variant(),
+            captured.to_variant(),
+        ];
+        // deferred, since the settings may still be bound by whoever started the rebind
+        match captured {
+            Some(_) => settings.call_deferred(StringName::from("finish_rebind"), &args),
+            None => settings.call_deferred(StringName::from("cancel_rebind"), &[]),
+        };
+    }
+}
+
+impl RebindListener {
+    /// `Some(None)` cancels the rebind, `None` keeps waiting. Captured events are copies that match any device and carry no pressed state
+    fn capture(&self, event: Gd<InputEvent>) -> Option<Option<Gd<InputEvent>>> {
+        if let Ok(key) = event.clone().try_cast::<InputEventKey>() {
+            if !key.is_pressed() || key.is_echo() {
+                return None;
+            }
+            if key.get_keycode() == Key::ESCAPE {
+                return Some(None);
+            }
+            let mut bound = InputEventKey::new_gd();
+            bound.set_keycode(key.get_keycode());
+            bound.set_physical_keycode(key.get_physical_keycode());
+            bound.set_key_label(key.get_key_label());
+            bound.set_unicode(key.get_unicode());
+            return Some(Some(Self::any_device(bound.upcast())));
+        }
+        if let Ok(button) = event.clone().try_cast::<InputEventMouseButton>() {
+            if !button.is_pressed() {
+                return None;
+            }
+            let mut bound = InputEventMouseButton::new_gd();
+            bound.set_button_index(button.get_button_index());
+            return Some(Some(Self::any_device(bound.upcast())));
+        }
+        if let Ok(button) = event.clone().try_cast::<InputEventJoypadButton>() {
+            if !button.is_pressed() {
+                return None;
+            }
+            let mut bound = InputEventJoypadButton::new_gd();
+            bound.set_button_index(button.get_button_index());
+            return Some(Some(Self::any_device(bound.upcast())));
+        }
+        if let Ok(motion) = event.try_cast::<InputEventJoypadMotion>() {
+            let value = motion.get_axis_value();
+            if value.abs() < self.axis_threshold {
+                return None;
+            }
+            // binds only care about the direction of the axis
+            let mut bound = InputEventJoypadMotion::new_gd();
+            bound.set_axis(motion.get_axis());
+            bound.set_axis_value(value.signum());
+            return Some(Some(Self::any_device(bound.upcast())));
+        }
+        None
+    }
+
+    fn any_device(mut event: Gd<InputEvent>) -> Gd<InputEvent> {
+        event.set_device(DEVICE_ALL);
+        event
+    }
+}
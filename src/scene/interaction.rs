@@ -1,8 +1,10 @@
 use godot::engine::{
-    Area3D, CharacterBody3D, IArea3D, IRayCast3D, RayCast3D, RigidBody3D, StaticBody3D,
+    Area3D, CharacterBody3D, IArea3D, IRayCast3D, InputEvent, RayCast3D, RigidBody3D, StaticBody3D,
 };
 use godot::prelude::*;
 
+use crate::scene::input::{haptics_manager::HapticsManager, input_context::InputContextStack};
+
 // these are accessed by calling .clone(). Normally I'd dislike this, but StringName is ref-counted so duplicating it is almost completely free
 const METHOD_SELECT: &str = "on_select";
 const METHOD_DESELECT: &str = "on_deselect";
@@ -20,6 +22,9 @@ struct InteractRaycast3D {
     filter_groups: PackedStringArray,
     #[var]
     target: Option<Gd<Node3D>>,
+    /// when set, interacts on this action by itself, as long as the action is live in the `InputContextStack`
+    #[export]
+    interact_action: StringName,
 
     base: Base<RayCast3D>,
 }
@@ -31,6 +36,9 @@ struct InteractArea3D {
     filter_groups: PackedStringArray,
     #[var]
     target: Option<Gd<Node3D>>,
+    /// when set, interacts on this action by itself, as long as the action is live in the `InputContextStack`
+    #[export]
+    interact_action: StringName,
 
     base: Base<Area3D>,
 }
@@ -42,6 +50,9 @@ struct InteractionObjectArea3D {
     is_active: bool,
     #[export]
     active_name: GString,
+    /// name of a rumble preset played when interacted with
+    #[export]
+    interact_rumble: StringName,
 
     base: Base<Area3D>,
 }
@@ -53,6 +64,9 @@ struct InteractionObjectStaticBody3D {
     is_active: bool,
     #[export]
     active_name: GString,
+    /// name of a rumble preset played when interacted with
+    #[export]
+    interact_rumble: StringName,
 
     base: Base<StaticBody3D>,
 }
@@ -64,6 +78,9 @@ struct InteractionObjectCharacterBody3D {
     is_active: bool,
     #[export]
     active_name: GString,
+    /// name of a rumble preset played when interacted with
+    #[export]
+    interact_rumble: StringName,
 
     base: Base<CharacterBody3D>,
 }
@@ -74,6 +91,9 @@ struct InteractionObjectRigidBody3D {
     is_active: bool,
     #[export]
     active_name: GString,
+    /// name of a rumble preset played when interacted with
+    #[export]
+    interact_rumble: StringName,
 
     base: Base<RigidBody3D>,
 }
@@ -99,6 +119,27 @@ fn is_active_interactable_object(node: Gd<Node>) -> bool {
     true
 }
 
+/// interacting is always allowed without an action, since the game decides when to call `do_interact`
+fn is_interact_live(action: &StringName) -> bool {
+    action.is_empty()
+        || InputContextStack::singleton()
+            .bind()
+            .is_action_live(action.clone())
+}
+
+fn is_interact_event(event: &Gd<InputEvent>, action: &StringName) -> bool {
+    !action.is_empty() && event.is_action_pressed(action.clone())
+}
+
+fn play_interact_rumble(preset_name: &StringName) {
+    if preset_name.is_empty() {
+        return;
+    }
+    HapticsManager::singleton()
+        .bind_mut()
+        .play(preset_name.clone());
+}
+
 #[godot_api]
 impl InteractRaycast3D {
     #[signal]
@@ -106,6 +147,9 @@ impl InteractRaycast3D {
 
     #[func]
     fn do_interact(&mut self) {
+        if !is_interact_live(&self.interact_action) {
+            return;
+        }
         if let Some(target) = self.target.as_mut() {
             if target.is_instance_valid() {
                 target.call_deferred(StringName::from(METHOD_INTERACT), &[]);
@@ -115,6 +159,12 @@ impl InteractRaycast3D {
 }
 #[godot_api]
 impl IRayCast3D for InteractRaycast3D {
+    fn unhandled_input(&mut self, event: Gd<InputEvent>) {
+        if is_interact_event(&event, &self.interact_action) {
+            self.do_interact();
+        }
+    }
+
     fn physics_process(&mut self, _delta: f64) {
         if let Some(collider) = self.base().get_collider() {
             let mut option_typed: Result<Gd<Node3D>, Gd<Object>> = collider.try_cast();
@@ -173,6 +223,9 @@ impl InteractArea3D {
     fn can_interact(is_able_to_interact: bool) {}
     #[func]
     fn do_interact(&mut self) {
+        if !is_interact_live(&self.interact_action) {
+            return;
+        }
         if let Some(target) = self.target.as_mut() {
             target.call_deferred(METHOD_INTERACT.into(), &[]);
         }
@@ -181,6 +234,12 @@ impl InteractArea3D {
 
 #[godot_api]
 impl IArea3D for InteractArea3D {
+    fn unhandled_input(&mut self, event: Gd<InputEvent>) {
+        if is_interact_event(&event, &self.interact_action) {
+            self.do_interact();
+        }
+    }
+
     fn physics_process(&mut self, _delta: f64) {
         let mut target_buffer: Array<Gd<Node3D>> = Array::new();
         target_buffer.extend_array(self.base().get_overlapping_bodies());
@@ -278,6 +337,7 @@ impl InteractionObjectArea3D {
         }
         self.base_mut()
             .emit_signal(StringName::from(SIGNAL_ON_INTERACT), &[]);
+        play_interact_rumble(&self.interact_rumble);
     }
 
     #[func]
@@ -324,6 +384,7 @@ impl InteractionObjectStaticBody3D {
         }
         self.base_mut()
             .emit_signal(StringName::from(SIGNAL_ON_INTERACT), &[]);
+        play_interact_rumble(&self.interact_rumble);
     }
     #[func]
     fn get_active(&self) -> bool {
@@ -369,6 +430,7 @@ impl InteractionObjectCharacterBody3D {
         }
         self.base_mut()
             .emit_signal(StringName::from(SIGNAL_ON_INTERACT), &[]);
+        play_interact_rumble(&self.interact_rumble);
     }
     #[func]
     fn get_active(&self) -> bool {
@@ -414,6 +476,7 @@ impl InteractionObjectRigidBody3D {
         }
         self.base_mut()
             .emit_signal(StringName::from(SIGNAL_ON_INTERACT), &[]);
+        play_interact_rumble(&self.interact_rumble);
     }
 
     #[func]
@@ -8,9 +8,17 @@ pub mod godot_replacements;
 pub mod input;
 pub mod interaction;
 pub mod procedural_meshes;
+pub mod save_bundle;
+pub mod save_crypto;
+pub mod save_io;
+pub mod save_migration;
+pub mod save_slots;
+pub mod save_worker;
+pub mod scene_persistence;
 pub mod serialization;
 pub mod signals;
 pub mod state_machine;
+pub mod tagged_json;
 pub mod utility_nodes;
 pub mod vfx_stack;
 pub mod gui;
@@ -18,9 +26,19 @@ pub mod gui;
 pub fn register_singletons() {
     game_globals::register_singleton();
     dialog::register_singleton();
+    save_slots::register_singleton();
+    input::input_device::register_singleton();
+    input::haptics_manager::register_singleton();
+    input::mouse_capture::register_singleton();
+    input::input_context::register_singleton();
 }
 
 pub fn unregister_singletons() {
     game_globals::unregister_singleton();
     dialog::unregister_singleton();
+    save_slots::unregister_singleton();
+    input::input_device::unregister_singleton();
+    input::haptics_manager::unregister_singleton();
+    input::mouse_capture::unregister_singleton();
+    input::input_context::unregister_singleton();
 }
@@ -0,0 +1,372 @@
+//! Moving player progress between machines.
+//!
+//! A bundle is a zip archive holding everything Sqore stores under `user://` (settings and the blackboard under `user://core/`, and the save slots) along with a `manifest.json`:
+//!
+//! ```json
+//! {
+//!     "bundle_version": 1,
+//!     "game_version": "1.2.0",
+//!     "created": 1718000000,
+//!     "files": { "core/audio.json": { "sha256": "...", "modified": 1717999000, "size": 120 } }
+//! }
+//! ```
+//!
+//! Files are copied byte for byte, so encrypted or signed saves stay that way. Pointing export and import at a synced folder works as a simple local stand-in for cloud saves.
+
+use godot::{
+    engine::{
+        global::Error, hashing_context::HashType, DirAccess, FileAccess, HashingContext, Json,
+        Time, ZipPacker, ZipReader,
+    },
+    prelude::*,
+};
+
+use super::{
+    dialog::core_dialog::SqoreDialog, game_globals::Sqore, save_io, save_slots::SaveSlotManager,
+    serialization::SaveDataBuilder,
+};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const CORE_DIRECTORY: &str = "user://core";
+const ROOT_CORE: &str = "core";
+const ROOT_SAVES: &str = "saves";
+
+const MANIFEST_VERSION: &str = "bundle_version";
+const MANIFEST_GAME_VERSION: &str = "game_version";
+const MANIFEST_CREATED: &str = "created";
+const MANIFEST_FILES: &str = "files";
+const FILE_SHA256: &str = "sha256";
+const FILE_MODIFIED: &str = "modified";
+const FILE_SIZE: &str = "size";
+
+/// What `import_bundle` does with a local file that differs from the one in the bundle
+#[derive(GodotConvert, Var, Export, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[godot(via=i32)]
+pub enum BundleConflictPolicy {
+    /// Keeps the local file if it was modified after the bundled one
+    #[default]
+    KeepNewest = 0,
+    /// Always replaces the local file
+    PreferBundle = 1,
+    /// Only imports files that don't exist locally
+    PreferLocal = 2,
+}
+
+/// Exports and imports all Sqore-managed user data as a single versioned archive. See the [module docs](self) for the layout
+#[derive(GodotClass)]
+#[class(init, base=Object)]
+pub struct SaveBundle {
+    base: Base<Object>,
+}
+
+#[godot_api]
+impl SaveBundle {
+    /// Bundles with a newer version than this can't be imported
+    pub const BUNDLE_VERSION: i64 = 1;
+
+    /// Saves the current globals and blackboard, then packs every managed file into the archive at `bundle_path`
+    #[func]
+    pub fn export_bundle(bundle_path: GString) -> bool {
+        Sqore::singleton().bind().save_globals();
+        SqoreDialog::singleton().bind_mut().save_blackboard();
+
+        let mut packer = ZipPacker::new_gd();
+        if packer.open(bundle_path.clone()) != Error::OK {
+            godot_warn!("Failed to create bundle file {}", bundle_path);
+            return false;
+        }
+        let mut files = Dictionary::new();
+        let mut success = true;
+        'roots: for (root, directory) in Self::get_roots() {
+            let mut relative_paths = Vec::new();
+            Self::collect_files(&directory, "", &mut relative_paths);
+            for relative in relative_paths {
+                let local_path = format!("{}/{}", directory, relative).to_godot();
+                let bytes = FileAccess::get_file_as_bytes(local_path.clone());
+                let bundle_file = format!("{}/{}", root, relative);
+                let written = packer.start_file(bundle_file.to_godot()) == Error::OK
+                    && packer.write_file(bytes.clone()) == Error::OK
+                    && packer.close_file() == Error::OK;
+                if !written {
+                    godot_warn!("Failed to add {} to bundle {}", local_path, bundle_path);
+                    success = false;
+                    break 'roots;
+                }
+                let mut entry = Dictionary::new();
+                entry.set(FILE_SHA256, Self::sha256_hex(bytes.as_slice()));
+                entry.set(
+                    FILE_MODIFIED,
+                    FileAccess::get_modified_time(local_path) as i64,
+                );
+                entry.set(FILE_SIZE, bytes.len() as i64);
+                files.set(bundle_file, entry);
+            }
+        }
+
+        let mut manifest = Dictionary::new();
+        manifest.set(MANIFEST_VERSION, Self::BUNDLE_VERSION);
+        manifest.set(MANIFEST_GAME_VERSION, SaveSlotManager::get_game_version());
+        manifest.set(
+            MANIFEST_CREATED,
+            Time::singleton().get_unix_time_from_system() as i64,
+        );
+        manifest.set(MANIFEST_FILES, files);
+        let manifest_text = Json::stringify_ex(manifest.to_variant())
+            .indent("\t".to_godot())
+            .done();
+        success = success
+            && packer.start_file(MANIFEST_FILE.to_godot()) == Error::OK
+            && packer.write_file(PackedByteArray::from(manifest_text.to_string().as_bytes()))
+                == Error::OK
+            && packer.close_file() == Error::OK;
+        packer.close();
+        if !success {
+            // a partial bundle would import as if files had been deleted
+            DirAccess::remove_absolute(bundle_path);
+        }
+        success
+    }
+
+    /// Reads the manifest of the bundle. Empty if the file is not a valid bundle
+    #[func]
+    pub fn read_manifest(bundle_path: GString) -> Dictionary {
+        let mut reader = ZipReader::new_gd();
+        if reader.open(bundle_path) != Error::OK {
+            return Dictionary::new();
+        }
+        let manifest = Self::read_manifest_from(&mut reader).unwrap_or_default();
+        reader.close();
+        manifest
+    }
+
+    /// Files in the bundle whose local copy differs and was modified after the bundled one. These are the files `KeepNewest` would skip, useful for asking the player before importing
+    #[func]
+    pub fn find_conflicts(bundle_path: GString) -> Array<GString> {
+        let manifest = Self::read_manifest(bundle_path);
+        let files = SaveDataBuilder::get_entry_from::<Dictionary>(&manifest, MANIFEST_FILES)
+            .unwrap_or_default();
+        let mut conflicts = Array::new();
+        for (bundle_file, entry) in files.iter_shared() {
+            let bundle_file = GString::from_variant(&bundle_file);
+            let Ok(entry) = Dictionary::try_from_variant(&entry) else {
+                continue;
+            };
+            let Some(local_path) = Self::get_local_path(&bundle_file.to_string()) else {
+                continue;
+            };
+            if Self::is_local_newer(&local_path, &entry) {
+                conflicts.push(bundle_file);
+            }
+        }
+        conflicts
+    }
+
+    /// Copies the bundled files into `user://`. Every file is checked against the manifest before anything is written, so a damaged bundle changes nothing. Previous versions of replaced files are kept as backups, see [SaveDataBuilder].
+    #[func]
+    pub fn import_bundle(bundle_path: GString, policy: BundleConflictPolicy) -> bool {
+        let mut reader = ZipReader::new_gd();
+        if reader.open(bundle_path.clone()) != Error::OK {
+            godot_warn!("Failed to open bundle {}", bundle_path);
+            return false;
+        }
+        let verified = Self::read_verified_files(&mut reader, &bundle_path);
+        reader.close();
+        let Some(files) = verified else {
+            return false;
+        };
+
+        let mut success = true;
+        let mut imported_core = false;
+        for (bundle_file, local_path, bytes, entry) in files {
+            if !Self::should_import(&local_path, &bytes, &entry, policy) {
+                continue;
+            }
+            if let Err(err) = SaveDataBuilder::write_raw_atomic(&local_path, &bytes) {
+                godot_warn!("Failed to import {}: {}", bundle_file, err.message);
+                success = false;
+                continue;
+            }
+            imported_core |= bundle_file.starts_with(ROOT_CORE);
+        }
+        if imported_core {
+            // deferred, this may be called from code already bound to either singleton
+            Sqore::singleton().call_deferred(StringName::from("reload_globals"), &[]);
+            SqoreDialog::singleton().call_deferred(StringName::from("load_blackboard"), &[]);
+        }
+        success
+    }
+
+    /// Bundle directory names and the local directories they are copied from
+    fn get_roots() -> Vec<(&'static str, String)> {
+        let saves_directory = SaveSlotManager::singleton()
+            .bind()
+            .get_saves_directory()
+            .to_string();
+        vec![
+            (ROOT_CORE, CORE_DIRECTORY.to_string()),
+            (
+                ROOT_SAVES,
+                saves_directory.trim_end_matches('/').to_string(),
+            ),
+        ]
+    }
+
+    /// Maps a path inside the bundle to the local file, rejecting paths that would escape the managed directories
+    fn get_local_path(bundle_file: &str) -> Option<GString> {
+        let (root, relative) = bundle_file.split_once('/')?;
+        if relative.is_empty()
+            || relative
+                .split('/')
+                .any(|part| part.is_empty() || part == "..")
+        {
+            return None;
+        }
+        let (_, directory) = Self::get_roots()
+            .into_iter()
+            .find(|(name, _)| *name == root)?;
+        Some(format!("{}/{}", directory, relative).to_godot())
+    }
+
+    fn collect_files(directory: &str, relative: &str, files: &mut Vec<String>) {
+        let path = if relative.is_empty() {
+            directory.to_string()
+        } else {
+            format!("{}/{}", directory, relative)
+        };
+        let join = |name: &GString| {
+            if relative.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}/{}", relative, name)
+            }
+        };
+        for file in DirAccess::get_files_at(path.to_godot()).as_slice() {
+            if !save_io::is_backup_or_temp(&file.to_string()) {
+                files.push(join(file));
+            }
+        }
+        for sub_directory in DirAccess::get_directories_at(path.to_godot()).as_slice() {
+            Self::collect_files(directory, &join(sub_directory), files);
+        }
+    }
+
+    fn read_manifest_from(reader: &mut Gd<ZipReader>) -> Option<Dictionary> {
+        if !reader.get_files().contains(&MANIFEST_FILE.to_godot()) {
+            return None;
+        }
+        let bytes = reader.read_file(MANIFEST_FILE.to_godot());
+        let text = std::str::from_utf8(bytes.as_slice()).ok()?;
+        Dictionary::try_from_variant(&Json::parse_string(text.to_godot())).ok()
+    }
+
+    /// Reads every file listed in the manifest, returning `None` if the bundle can't be imported
+    fn read_verified_files(
+        reader: &mut Gd<ZipReader>,
+        bundle_path: &GString,
+    ) -> Option<Vec<(String, GString, Vec<u8>, Dictionary)>> {
+        let Some(manifest) = Self::read_manifest_from(reader) else {
+            godot_warn!("Bundle {} has no valid manifest", bundle_path);
+            return None;
+        };
+        let version = Self::get_manifest_int(&manifest, MANIFEST_VERSION);
+        if version > Self::BUNDLE_VERSION {
+            godot_warn!(
+                "Bundle {} is version {}, which is newer than the supported version {}",
+                bundle_path,
+                version,
+                Self::BUNDLE_VERSION
+            );
+            return None;
+        }
+        let entries = SaveDataBuilder::get_entry_from::<Dictionary>(&manifest, MANIFEST_FILES)
+            .unwrap_or_default();
+        let mut files = Vec::new();
+        for (bundle_file, entry) in entries.iter_shared() {
+            let bundle_file = GString::from_variant(&bundle_file).to_string();
+            let entry = Dictionary::try_from_variant(&entry).unwrap_or_default();
+            let Some(local_path) = Self::get_local_path(&bundle_file) else {
+                godot_warn!(
+                    "Bundle {} contains invalid path {}",
+                    bundle_path,
+                    bundle_file
+                );
+                return None;
+            };
+            let expected =
+                SaveDataBuilder::get_entry_from::<GString>(&entry, FILE_SHA256).unwrap_or_default();
+            let bytes = reader.read_file(bundle_file.to_godot()).to_vec();
+            if Self::sha256_hex(&bytes) != expected.to_string() {
+                godot_warn!(
+                    "File {} in bundle {} does not match its hash, the bundle is damaged",
+                    bundle_file,
+                    bundle_path
+                );
+                return None;
+            }
+            files.push((bundle_file, local_path, bytes, entry));
+        }
+        Some(files)
+    }
+
+    fn should_import(
+        local_path: &GString,
+        bytes: &[u8],
+        entry: &Dictionary,
+        policy: BundleConflictPolicy,
+    ) -> bool {
+        if !FileAccess::file_exists(local_path.clone()) {
+            return true;
+        }
+        let local_bytes = FileAccess::get_file_as_bytes(local_path.clone());
+        if local_bytes.as_slice() == bytes {
+            return false;
+        }
+        match policy {
+            BundleConflictPolicy::PreferBundle => true,
+            BundleConflictPolicy::PreferLocal => false,
+            BundleConflictPolicy::KeepNewest => {
+                let keep = Self::is_local_newer(local_path, entry);
+                if keep {
+                    godot_print!("Keeping {}, it is newer than the bundled copy", local_path);
+                }
+                !keep
+            }
+        }
+    }
+
+    fn is_local_newer(local_path: &GString, entry: &Dictionary) -> bool {
+        if !FileAccess::file_exists(local_path.clone()) {
+            return false;
+        }
+        let local_bytes = FileAccess::get_file_as_bytes(local_path.clone());
+        let expected =
+            SaveDataBuilder::get_entry_from::<GString>(entry, FILE_SHA256).unwrap_or_default();
+        if Self::sha256_hex(local_bytes.as_slice()) == expected.to_string() {
+            return false;
+        }
+        let bundled_modified = Self::get_manifest_int(entry, FILE_MODIFIED);
+        FileAccess::get_modified_time(local_path.clone()) as i64 > bundled_modified
+    }
+
+    fn sha256_hex(bytes: &[u8]) -> String {
+        let mut hashing = HashingContext::new_gd();
+        hashing.start(HashType::SHA256);
+        // empty chunks are rejected by the engine
+        if !bytes.is_empty() {
+            hashing.update(PackedByteArray::from(bytes));
+        }
+        hashing
+            .finish()
+            .as_slice()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// JSON stores all numbers as floats, so integers in the manifest are read as floats and converted
+    fn get_manifest_int(dict: &Dictionary, key: &str) -> i64 {
+        SaveDataBuilder::get_entry_from::<f64>(dict, key)
+            .map(|value| value as i64)
+            .unwrap_or(0)
+    }
+}
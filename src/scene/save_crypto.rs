@@ -0,0 +1,169 @@
+//! Encryption and tamper detection for save files.
+//!
+//! Enabled through project settings under `addons/sqore/serialization/`. Signed files start with `SQHM` followed by an HMAC-SHA256 of the rest of the file. Encrypted files are AES-256-CBC with a random IV, and are always signed as well since unsigned ciphertext can be modified without being noticed.
+//!
+//! The secret ships with the game, so this only stops casual editing of saves, not a determined player.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use godot::{
+    engine::{aes_context::Mode, hashing_context::HashType, AesContext, Crypto, HashingContext},
+    prelude::*,
+};
+
+use super::game_globals::Sqore;
+
+const S_SECRET: &str = "serialization/secret";
+const S_ENCRYPT: &str = "serialization/encrypt_saves";
+const S_SIGN: &str = "serialization/sign_saves";
+
+const SIGNED_HEADER: &[u8; 4] = b"SQHM";
+const ENCRYPTED_HEADER: &[u8; 4] = b"SQEN";
+const DIGEST_LEN: usize = 32;
+const BLOCK_LEN: usize = 16;
+
+/// The missing secret is only reported once, rather than on every save and load
+static WARNED_EMPTY_SECRET: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenError {
+    /// The signature doesn't match, or signing is required and the file is unsigned
+    Tampered,
+    /// The file could not be decrypted
+    Corrupted,
+}
+
+pub struct SaveSecurity {
+    secret: Vec<u8>,
+    encrypt: bool,
+    sign: bool,
+}
+
+impl SaveSecurity {
+    pub fn from_project_settings() -> Self {
+        let secret = Sqore::get_or_init_default(S_SECRET, GString::new()).unwrap_or_default();
+        let encrypt = Sqore::get_or_init_default(S_ENCRYPT, false).unwrap_or(false);
+        let sign = Sqore::get_or_init_default(S_SIGN, false).unwrap_or(false);
+        if (encrypt || sign)
+            && secret.is_empty()
+            && !WARNED_EMPTY_SECRET.swap(true, Ordering::Relaxed)
+        {
+            godot_warn!("Save encryption or signing is enabled, but \"addons/sqore/{}\" is empty. Please set a secret", S_SECRET);
+        }
+        Self {
+            secret: secret.to_string().into_bytes(),
+            encrypt,
+            sign: sign || encrypt,
+        }
+    }
+
+    /// Encrypts and signs the bytes, depending on the project settings
+    pub fn seal(&self, bytes: Vec<u8>) -> Vec<u8> {
+        let mut bytes = bytes;
+        if self.encrypt {
+            bytes = self.encrypt(&bytes);
+        }
+        if self.sign {
+            let digest = self.digest(&bytes);
+            let mut signed = SIGNED_HEADER.to_vec();
+            signed.extend_from_slice(&digest);
+            signed.extend_from_slice(&bytes);
+            bytes = signed;
+        }
+        bytes
+    }
+
+    /// Verifies and decrypts the bytes. Files that are neither signed nor encrypted pass through untouched, unless signing is required
+    pub fn open(&self, bytes: &[u8]) -> Result<Vec<u8>, OpenError> {
+        let mut payload = bytes;
+        if let Some(signed) = bytes.strip_prefix(SIGNED_HEADER) {
+            if signed.len() < DIGEST_LEN {
+                return Err(OpenError::Tampered);
+            }
+            let (digest, rest) = signed.split_at(DIGEST_LEN);
+            let expected = self.digest(rest);
+            let matches = Crypto::new_gd().constant_time_compare(
+                PackedByteArray::from(digest),
+                PackedByteArray::from(expected.as_slice()),
+            );
+            if !matches {
+                return Err(OpenError::Tampered);
+            }
+            payload = rest;
+        } else if self.sign {
+            return Err(OpenError::Tampered);
+        }
+        if let Some(encrypted) = payload.strip_prefix(ENCRYPTED_HEADER) {
+            return self.decrypt(encrypted).ok_or(OpenError::Corrupted);
+        }
+        Ok(payload.to_vec())
+    }
+
+    fn digest(&self, bytes: &[u8]) -> Vec<u8> {
+        Crypto::new_gd()
+            .hmac_digest(
+                HashType::SHA256,
+                PackedByteArray::from(self.derive_key(b"sign").as_slice()),
+                PackedByteArray::from(bytes),
+            )
+            .to_vec()
+    }
+
+    fn encrypt(&self, bytes: &[u8]) -> Vec<u8> {
+        let iv = Crypto::new_gd().generate_random_bytes(BLOCK_LEN as i32);
+        // PKCS#7 padding, AES only works on whole blocks
+        let padding = BLOCK_LEN - bytes.len() % BLOCK_LEN;
+        let mut padded = bytes.to_vec();
+        padded.resize(bytes.len() + padding, padding as u8);
+
+        let mut aes = AesContext::new_gd();
+        aes.start_ex(
+            Mode::CBC_ENCRYPT,
+            PackedByteArray::from(self.derive_key(b"encrypt").as_slice()),
+        )
+        .iv(iv.clone())
+        .done();
+        let encrypted = aes.update(PackedByteArray::from(padded.as_slice()));
+        aes.finish();
+
+        let mut result = ENCRYPTED_HEADER.to_vec();
+        result.extend_from_slice(iv.as_slice());
+        result.extend_from_slice(encrypted.as_slice());
+        result
+    }
+
+    fn decrypt(&self, bytes: &[u8]) -> Option<Vec<u8>> {
+        if bytes.len() < BLOCK_LEN * 2 || bytes.len() % BLOCK_LEN != 0 {
+            return None;
+        }
+        let (iv, encrypted) = bytes.split_at(BLOCK_LEN);
+        let mut aes = AesContext::new_gd();
+        aes.start_ex(
+            Mode::CBC_DECRYPT,
+            PackedByteArray::from(self.derive_key(b"encrypt").as_slice()),
+        )
+        .iv(PackedByteArray::from(iv))
+        .done();
+        let mut decrypted = aes.update(PackedByteArray::from(encrypted)).to_vec();
+        aes.finish();
+
+        let padding = *decrypted.last()? as usize;
+        if padding == 0 || padding > BLOCK_LEN || padding > decrypted.len() {
+            return None;
+        }
+        decrypted.truncate(decrypted.len() - padding);
+        Some(decrypted)
+    }
+
+    /// Separate keys for encryption and signing, both 256 bits
+    fn derive_key(&self, purpose: &[u8]) -> Vec<u8> {
+        let mut hashing = HashingContext::new_gd();
+        hashing.start(HashType::SHA256);
+        hashing.update(PackedByteArray::from(purpose));
+        // empty chunks are rejected by the engine
+        if !self.secret.is_empty() {
+            hashing.update(PackedByteArray::from(self.secret.as_slice()));
+        }
+        hashing.finish().to_vec()
+    }
+}
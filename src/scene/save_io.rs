@@ -0,0 +1,222 @@
+//! File access for save data that is safe to use from worker threads.
+//!
+//! Only uses `std` so that it never touches the engine off the main thread. Paths must already be globalized (see `ProjectSettings.globalize_path`). Every read or write of a save file holds a lock for that path, so concurrent requests to the same file run one after the other instead of interleaving.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+};
+
+const TEMP_SUFFIX: &str = ".tmp";
+const BACKUP_SUFFIX: &str = ".bak";
+
+static FILE_LOCKS: OnceLock<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> = OnceLock::new();
+
+/// A failed write. Keeps the kind of error so callers can tell problems such as missing permissions apart
+#[derive(Debug)]
+pub struct WriteFailure {
+    pub kind: io::ErrorKind,
+    pub message: String,
+}
+
+impl WriteFailure {
+    fn new(err: io::Error, context: String) -> Self {
+        Self {
+            kind: err.kind(),
+            message: format!("{}: {}", context, err),
+        }
+    }
+}
+
+/// Runs `action` while holding the lock for the path
+pub fn with_file_lock<T>(path: &Path, action: impl FnOnce() -> T) -> T {
+    let lock = {
+        let mut locks = FILE_LOCKS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        locks.entry(path.to_path_buf()).or_default().clone()
+    };
+    // a panic while saving shouldn't block the file forever
+    let _guard = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    action()
+}
+
+/// Whether the file name belongs to a backup or an unfinished write, rather than an actual save file
+pub fn is_backup_or_temp(file_name: &str) -> bool {
+    if file_name.ends_with(TEMP_SUFFIX) {
+        return true;
+    }
+    match file_name.rsplit_once(BACKUP_SUFFIX) {
+        Some((_, index)) => !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
+pub fn get_backup_path(path: &Path, index: i32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!("{}{}", BACKUP_SUFFIX, index));
+    PathBuf::from(name)
+}
+
+/// Writes the bytes to a temporary file and verifies them before replacing the file at `path`, keeping `backup_count` older versions (`.bak1` being the newest). `progress` is called after each step with the fraction done.
+pub fn write_atomic(
+    path: &Path,
+    bytes: &[u8],
+    backup_count: i32,
+    progress: impl Fn(f32),
+) -> Result<(), WriteFailure> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            WriteFailure::new(err, format!("Failed to create directory {:?}", parent))
+        })?;
+    }
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(TEMP_SUFFIX);
+    let temp_path = PathBuf::from(temp_name);
+    {
+        let mut file = fs::File::create(&temp_path).map_err(|err| {
+            WriteFailure::new(err, format!("Failed to access file {:?}", temp_path))
+        })?;
+        file.write_all(bytes)
+            .and_then(|_| file.sync_all())
+            .map_err(|err| {
+                WriteFailure::new(err, format!("Failed to write file {:?}", temp_path))
+            })?;
+    }
+    progress(0.4);
+
+    // re-read to catch partial writes (such as a full disk)
+    let written = fs::read(&temp_path).unwrap_or_default();
+    if written != bytes {
+        let _ = fs::remove_file(&temp_path);
+        return Err(WriteFailure {
+            kind: io::ErrorKind::InvalidData,
+            message: format!("Failed to verify written file {:?}", temp_path),
+        });
+    }
+    progress(0.6);
+
+    // rotate backups, the primary file is copied so a valid version exists at all times
+    if backup_count > 0 && path.exists() {
+        let _ = fs::remove_file(get_backup_path(path, backup_count));
+        for index in (1..backup_count).rev() {
+            let from = get_backup_path(path, index);
+            if from.exists() {
+                let _ = fs::rename(&from, get_backup_path(path, index + 1));
+            }
+        }
+        let _ = fs::copy(path, get_backup_path(path, 1));
+    }
+    progress(0.8);
+
+    fs::rename(&temp_path, path)
+        .map_err(|err| WriteFailure::new(err, format!("Failed to replace file {:?}", path)))?;
+    progress(1.0);
+    Ok(())
+}
+
+/// Whether the file or any of its backups exists
+pub fn exists_with_backups(path: &Path, backup_count: i32) -> bool {
+    path.is_file() || (1..=backup_count).any(|index| get_backup_path(path, index).is_file())
+}
+
+/// Reads the file followed by each of its backups, newest first
+pub fn read_with_backups(path: &Path, backup_count: i32) -> Vec<io::Result<Vec<u8>>> {
+    let mut files = vec![fs::read(path)];
+    for index in 1..=backup_count {
+        files.push(fs::read(get_backup_path(path, index)));
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for one test, removed again when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("sqore_save_io_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn save(path: &Path, bytes: &[u8], backup_count: i32) {
+        write_atomic(path, bytes, backup_count, |_| ()).unwrap();
+    }
+
+    #[test]
+    fn rotation_keeps_backup_count_with_bak1_newest() {
+        let dir = TempDir::new("rotation");
+        let path = dir.0.join("game.sav");
+        for version in ["v1", "v2", "v3", "v4", "v5"] {
+            save(&path, version.as_bytes(), 3);
+        }
+        assert_eq!(fs::read(&path).unwrap(), b"v5");
+        assert_eq!(fs::read(get_backup_path(&path, 1)).unwrap(), b"v4");
+        assert_eq!(fs::read(get_backup_path(&path, 2)).unwrap(), b"v3");
+        assert_eq!(fs::read(get_backup_path(&path, 3)).unwrap(), b"v2");
+        assert!(!get_backup_path(&path, 4).exists());
+        assert!(!dir.0.join("game.sav.tmp").exists());
+    }
+
+    #[test]
+    fn failed_write_leaves_original_intact() {
+        let dir = TempDir::new("failed_write");
+        let path = dir.0.join("game.sav");
+        save(&path, b"original", 2);
+        // the temporary file can't be created where a directory is in the way
+        fs::create_dir(dir.0.join("game.sav.tmp")).unwrap();
+        assert!(write_atomic(&path, b"replacement", 2, |_| ()).is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"original");
+        assert!(!get_backup_path(&path, 1).exists());
+    }
+
+    #[test]
+    fn read_falls_back_to_newest_backup() {
+        let dir = TempDir::new("read_backups");
+        let path = dir.0.join("game.sav");
+        for version in ["v1", "v2", "v3"] {
+            save(&path, version.as_bytes(), 2);
+        }
+
+        fs::remove_file(&path).unwrap();
+        let files = read_with_backups(&path, 2);
+        assert_eq!(files.len(), 3);
+        assert_eq!(
+            files[0].as_ref().unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(files[1].as_ref().unwrap(), b"v2");
+        assert_eq!(files[2].as_ref().unwrap(), b"v1");
+        assert!(exists_with_backups(&path, 2));
+
+        fs::write(&path, b"").unwrap();
+        let files = read_with_backups(&path, 2);
+        assert!(files[0].as_ref().unwrap().is_empty());
+        assert_eq!(files[1].as_ref().unwrap(), b"v2");
+    }
+
+    #[test]
+    fn recognizes_backup_and_temp_names() {
+        assert!(is_backup_or_temp("game.sav.bak1"));
+        assert!(is_backup_or_temp("game.sav.tmp"));
+        assert!(!is_backup_or_temp("game.sav"));
+        assert!(!is_backup_or_temp("game.bak"));
+    }
+}
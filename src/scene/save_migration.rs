@@ -0,0 +1,83 @@
+//! Chaining schema migrations to bring save data up to the current version.
+//!
+//! Only uses `std`, so the version logic doesn't depend on how steps or data are represented. [SaveDataBuilder](super::serialization::SaveDataBuilder) keeps the registered steps and runs them through [migrate].
+
+use std::collections::BTreeMap;
+
+/// The data is from a newer version than the game supports, so it can't be migrated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NewerVersion {
+    pub version: i64,
+    pub target_version: i64,
+}
+
+/// Runs every step between `version` and `target_version` in order, passing the data from one step to the next. `steps` maps the version a step upgrades from to the step.
+///
+/// Versions without a step are skipped, since nothing about the data changed in them.
+pub fn migrate<T, D>(
+    steps: &BTreeMap<i64, T>,
+    version: i64,
+    target_version: i64,
+    data: D,
+    mut apply: impl FnMut(&T, D) -> D,
+) -> Result<D, NewerVersion> {
+    if version > target_version {
+        return Err(NewerVersion {
+            version,
+            target_version,
+        });
+    }
+    Ok(steps
+        .range(version..target_version)
+        .fold(data, |data, (_, step)| apply(step, data)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(from_versions: &[i64]) -> BTreeMap<i64, i64> {
+        from_versions
+            .iter()
+            .map(|&version| (version, version))
+            .collect()
+    }
+
+    fn run(
+        steps: &BTreeMap<i64, i64>,
+        version: i64,
+        target: i64,
+    ) -> Result<Vec<i64>, NewerVersion> {
+        migrate(steps, version, target, Vec::new(), |step, mut applied| {
+            applied.push(*step);
+            applied
+        })
+    }
+
+    #[test]
+    fn chains_steps_from_v1_to_v3_in_order() {
+        assert_eq!(run(&steps(&[0, 1, 2, 3]), 1, 3), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn skips_versions_without_a_step() {
+        assert_eq!(run(&steps(&[1, 3]), 1, 4), Ok(vec![1, 3]));
+        assert_eq!(run(&steps(&[]), 0, 2), Ok(vec![]));
+    }
+
+    #[test]
+    fn current_version_runs_nothing() {
+        assert_eq!(run(&steps(&[0, 1, 2]), 3, 3), Ok(vec![]));
+    }
+
+    #[test]
+    fn newer_than_current_is_an_error() {
+        assert_eq!(
+            run(&steps(&[0, 1]), 4, 2),
+            Err(NewerVersion {
+                version: 4,
+                target_version: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use godot::{
+    engine::{
+        global::Error, DirAccess, Engine, FileAccess, Image, ImageTexture, Json, ProjectSettings,
+        Time, Viewport,
+    },
+    prelude::*,
+};
+
+use super::{game_globals::Sqore, serialization::SaveDataBuilder};
+
+const S_SAVES_DIRECTORY: &str = "save_slots/directory";
+const S_THUMBNAIL_WIDTH: &str = "save_slots/thumbnail_width";
+
+const DATA_FILE: &str = "data.json";
+const META_FILE: &str = "meta.json";
+const THUMBNAIL_FILE: &str = "thumbnail.png";
+
+pub fn register_singleton() {
+    Engine::singleton().register_singleton(
+        StringName::from(SaveSlotManager::SINGLETON_NAME),
+        SaveSlotManager::new_alloc().upcast(),
+    );
+}
+
+pub fn unregister_singleton() {
+    Engine::singleton().unregister_singleton(StringName::from(SaveSlotManager::SINGLETON_NAME));
+}
+
+/// Manages save slots on top of [SaveDataBuilder]. Each slot is a directory holding the save data, a small metadata file, and an optional thumbnail, so that a load menu can list saves without parsing the full save data.
+///
+/// Slots are identified by name. Numbered slots are simply named `slot_<number>`, see `get_numbered_slot`.
+#[derive(GodotClass)]
+#[class(base=Object)]
+pub struct SaveSlotManager {
+    #[var]
+    saves_directory: GString,
+    #[var]
+    thumbnail_width: i32,
+    base: Base<Object>,
+}
+
+#[godot_api]
+impl IObject for SaveSlotManager {
+    fn init(base: Base<Self::Base>) -> Self {
+        let saves_directory =
+            Sqore::get_or_init_default(S_SAVES_DIRECTORY, "user://saves".to_godot())
+                .unwrap_or("user://saves".to_godot());
+        let thumbnail_width = Sqore::get_or_init_default(S_THUMBNAIL_WIDTH, 320).unwrap_or(320);
+        Self {
+            saves_directory,
+            thumbnail_width,
+            base,
+        }
+    }
+}
+
+#[godot_api]
+impl SaveSlotManager {
+    pub const SINGLETON_NAME: &'static str = "SaveSlotManager";
+
+    pub const META_TIMESTAMP: &'static str = "timestamp";
+    pub const META_DATETIME: &'static str = "datetime";
+    pub const META_PLAYTIME: &'static str = "playtime";
+    pub const META_CHAPTER: &'static str = "chapter";
+    pub const META_GAME_VERSION: &'static str = "game_version";
+    pub const META_SLOT: &'static str = "slot";
+
+    /// The slot name used for a numbered slot
+    #[func]
+    pub fn get_numbered_slot(index: i64) -> GString {
+        format!("slot_{}", index).to_godot()
+    }
+
+    #[func]
+    pub fn get_slot_directory(&self, slot: GString) -> GString {
+        format!(
+            "{}/{}",
+            self.saves_directory.to_string().trim_end_matches('/'),
+            Self::sanitize_slot_name(&slot.to_string())
+        )
+        .to_godot()
+    }
+
+    /// Whether the slot has save data, counting a slot whose data file is gone but still has a backup to load from
+    #[func]
+    pub fn has_slot(&self, slot: GString) -> bool {
+        SaveDataBuilder::exists_with_backups(&self.get_slot_file(&slot, DATA_FILE))
+    }
+
+    /// Writes the save data and metadata to the slot, replacing anything already there. The thumbnail is optional, see `capture_thumbnail`.
+    #[func]
+    pub fn save_slot(
+        &mut self,
+        slot: GString,
+        mut data: Gd<SaveDataBuilder>,
+        chapter: GString,
+        playtime: f64,
+        thumbnail: Option<Gd<Image>>,
+    ) -> bool {
+        if !data.bind_mut().save(self.get_slot_file(&slot, DATA_FILE)) {
+            godot_warn!("Failed to write save data for slot \"{}\"", slot);
+            return false;
+        }
+        let time = Time::singleton();
+        let mut meta = Dictionary::new();
+        meta.set(Self::META_TIMESTAMP, time.get_unix_time_from_system());
+        meta.set(Self::META_DATETIME, time.get_datetime_string_from_system());
+        meta.set(Self::META_PLAYTIME, playtime);
+        meta.set(Self::META_CHAPTER, chapter);
+        meta.set(Self::META_GAME_VERSION, Self::get_game_version());
+        let meta_text = Json::stringify(meta.to_variant()).to_string();
+        if SaveDataBuilder::write_atomic(
+            &self.get_slot_file(&slot, META_FILE),
+            meta_text.as_bytes(),
+        )
+        .is_err()
+        {
+            return false;
+        }
+
+        let thumbnail_path = self.get_slot_file(&slot, THUMBNAIL_FILE);
+        match thumbnail {
+            Some(mut image) => {
+                if image.save_png(thumbnail_path) != Error::OK {
+                    godot_warn!("Failed to write thumbnail for slot \"{}\"", slot);
+                }
+            }
+            None => {
+                // don't leave a thumbnail from an older save behind
+                if FileAccess::file_exists(thumbnail_path.clone()) {
+                    DirAccess::remove_absolute(thumbnail_path);
+                }
+            }
+        }
+        true
+    }
+
+    /// Loads the full save data of the slot. The caller is responsible for freeing the builder
+    #[func]
+    pub fn load_slot(&self, slot: GString) -> Option<Gd<SaveDataBuilder>> {
+        SaveDataBuilder::try_load_file(self.get_slot_file(&slot, DATA_FILE))
+    }
+
+    /// Reads only the metadata of the slot. Empty if the slot does not exist
+    #[func]
+    pub fn get_slot_meta(&self, slot: GString) -> Dictionary {
+        let path = self.get_slot_file(&slot, META_FILE);
+        let Ok(mut meta) = SaveDataBuilder::read_with_backups(&path) else {
+            return Dictionary::new();
+        };
+        meta.set(Self::META_SLOT, slot);
+        meta
+    }
+
+    #[func]
+    pub fn get_slot_thumbnail(&self, slot: GString) -> Option<Gd<ImageTexture>> {
+        let path = self.get_slot_file(&slot, THUMBNAIL_FILE);
+        if !FileAccess::file_exists(path.clone()) {
+            return None;
+        }
+        let image = Image::load_from_file(path)?;
+        ImageTexture::create_from_image(image)
+    }
+
+    /// Names of all existing slots, including those that can only be loaded from a backup
+    #[func]
+    pub fn list_slots(&self) -> Array<GString> {
+        let slots = DirAccess::get_directories_at(self.saves_directory.clone());
+        Array::from_iter(
+            slots
+                .as_slice()
+                .iter()
+                .filter(|slot| self.has_slot((*slot).clone()))
+                .cloned(),
+        )
+    }
+
+    /// Metadata of all existing slots, newest first. Each entry includes the slot name under the "slot" key
+    #[func]
+    pub fn list_slot_meta(&self) -> Array<Dictionary> {
+        let mut entries: Vec<(f64, Dictionary)> = self
+            .list_slots()
+            .iter_shared()
+            .map(|slot| {
+                let meta = self.get_slot_meta(slot);
+                let timestamp = SaveDataBuilder::get_entry_from::<f64>(&meta, Self::META_TIMESTAMP)
+                    .unwrap_or_default();
+                (timestamp, meta)
+            })
+            .collect();
+        entries.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Array::from_iter(entries.into_iter().map(|(_, meta)| meta))
+    }
+
+    #[func]
+    pub fn delete_slot(&self, slot: GString) -> bool {
+        let dir_path = self.get_slot_directory(slot.clone());
+        if !DirAccess::dir_exists_absolute(dir_path.clone()) {
+            return false;
+        }
+        for file in DirAccess::get_files_at(dir_path.clone()).as_slice() {
+            let path = format!("{}/{}", dir_path, file).to_godot();
+            if DirAccess::remove_absolute(path) != Error::OK {
+                godot_warn!("Failed to delete file \"{}\" from slot \"{}\"", file, slot);
+                return false;
+            }
+        }
+        DirAccess::remove_absolute(dir_path) == Error::OK
+    }
+
+    /// Copies one slot over another, replacing the target slot entirely
+    #[func]
+    pub fn copy_slot(&self, from: GString, to: GString) -> bool {
+        if !self.has_slot(from.clone()) {
+            godot_warn!("Cannot copy from missing slot \"{}\"", from);
+            return false;
+        }
+        let from_dir = self.get_slot_directory(from);
+        let to_dir = self.get_slot_directory(to.clone());
+        if self.has_slot(to.clone()) {
+            self.delete_slot(to.clone());
+        }
+        DirAccess::make_dir_recursive_absolute(to_dir.clone());
+        for file in DirAccess::get_files_at(from_dir.clone()).as_slice() {
+            let err = DirAccess::copy_absolute(
+                format!("{}/{}", from_dir, file).to_godot(),
+                format!("{}/{}", to_dir, file).to_godot(),
+            );
+            if err != Error::OK {
+                godot_warn!("Failed to copy file \"{}\" into slot \"{}\"", file, to);
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Grabs the current image of the viewport, scaled down to the configured thumbnail width
+    #[func]
+    pub fn capture_thumbnail(&self, viewport: Gd<Viewport>) -> Option<Gd<Image>> {
+        let mut image = viewport.get_texture()?.get_image()?;
+        let width = image.get_width();
+        if width > self.thumbnail_width && self.thumbnail_width > 0 {
+            let height = image.get_height() * self.thumbnail_width / width;
+            image.resize(self.thumbnail_width, height.max(1));
+        }
+        Some(image)
+    }
+
+    pub fn singleton() -> Gd<SaveSlotManager> {
+        let Some(vol) = Engine::singleton().get_singleton(StringName::from(Self::SINGLETON_NAME))
+        else {
+            panic!("Failed to find engine singleton for SaveSlotManager. You must access this after it is registered!");
+        };
+        let res_core: Result<Gd<SaveSlotManager>, Gd<_>> = vol.try_cast();
+        let Ok(core) = res_core else {
+            panic!(
+                "Failed to cast engine singleton for SaveSlotManager. This should never happen!"
+            );
+        };
+        core
+    }
+
+    fn get_slot_file(&self, slot: &GString, file: &str) -> GString {
+        format!("{}/{}", self.get_slot_directory(slot.clone()), file).to_godot()
+    }
+
+    /// The `application/config/version` project setting
+    pub fn get_game_version() -> GString {
+        let version =
+            ProjectSettings::singleton().get_setting(GString::from("application/config/version"));
+        GString::try_from_variant(&version).unwrap_or_default()
+    }
+
+    /// Keeps slot names from escaping the saves directory or using characters that are invalid in file names
+    fn sanitize_slot_name(name: &str) -> String {
+        let cleaned: String = name
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                _ => c,
+            })
+            .collect();
+        let cleaned = cleaned.trim_matches('.').trim();
+        if cleaned.is_empty() {
+            return String::from("_");
+        }
+        cleaned.to_string()
+    }
+}
@@ -0,0 +1,180 @@
+//! A single background thread for `save_async` and `load_async`.
+//!
+//! Only uses `std`, like [save_io](super::save_io). Jobs run one at a time in the order they were queued, so when the same file is saved twice the last request is the one left on disk. Results are sent back as plain values through the channel given with each job, and the main thread drains that channel.
+
+use std::{
+    io,
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Sender},
+        Mutex, OnceLock,
+    },
+    thread,
+};
+
+use super::save_io::{self, WriteFailure};
+
+static QUEUE: OnceLock<Mutex<Sender<Job>>> = OnceLock::new();
+
+pub enum Request {
+    /// Writes the bytes as given, they must already be sealed
+    Save(Vec<u8>),
+    Load,
+}
+
+pub struct Job {
+    /// The path as requested, passed back with every event
+    pub file_path: String,
+    /// The globalized path that is actually accessed
+    pub abs_path: PathBuf,
+    pub backup_count: i32,
+    pub request: Request,
+    pub events: Sender<JobEvent>,
+}
+
+pub enum JobEvent {
+    SaveProgress {
+        file_path: String,
+        progress: f32,
+    },
+    SaveFinished {
+        file_path: String,
+        result: Result<(), WriteFailure>,
+    },
+    /// The file followed by its backups, newest first
+    LoadFinished {
+        file_path: String,
+        files: Vec<io::Result<Vec<u8>>>,
+    },
+}
+
+/// Queues the job behind all earlier ones, starting the worker thread if needed. Gives the job back if the worker is gone
+pub fn submit(job: Job) -> Result<(), Job> {
+    let queue = QUEUE.get_or_init(|| Mutex::new(spawn_worker()));
+    let queue = queue
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    queue.send(job).map_err(|err| err.0)
+}
+
+fn spawn_worker() -> Sender<Job> {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    // if the thread can't start, the receiver is dropped with the closure and every submit hands its job back
+    let _ = thread::Builder::new()
+        .name("sqore_save_worker".into())
+        .spawn(move || {
+            for job in receiver {
+                run(job);
+            }
+        });
+    sender
+}
+
+fn run(job: Job) {
+    let Job {
+        file_path,
+        abs_path,
+        backup_count,
+        request,
+        events,
+    } = job;
+    // the builder may have been freed, in which case nobody is listening and sending fails
+    let event = match request {
+        Request::Save(bytes) => {
+            let result = save_io::with_file_lock(&abs_path, || {
+                save_io::write_atomic(&abs_path, &bytes, backup_count, |progress| {
+                    let _ = events.send(JobEvent::SaveProgress {
+                        file_path: file_path.clone(),
+                        progress,
+                    });
+                })
+            });
+            JobEvent::SaveFinished { file_path, result }
+        }
+        Request::Load => {
+            let files = save_io::with_file_lock(&abs_path, || {
+                save_io::read_with_backups(&abs_path, backup_count)
+            });
+            JobEvent::LoadFinished { file_path, files }
+        }
+    };
+    let _ = events.send(event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A fresh directory for one test, removed again when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "sqore_save_worker_{}_{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn job(path: &PathBuf, request: Request, events: &Sender<JobEvent>) -> Job {
+        Job {
+            file_path: path.to_string_lossy().into_owned(),
+            abs_path: path.clone(),
+            backup_count: 1,
+            request,
+            events: events.clone(),
+        }
+    }
+
+    fn wait_finished(receiver: &mpsc::Receiver<JobEvent>) -> JobEvent {
+        receiver
+            .iter()
+            .find(|event| !matches!(event, JobEvent::SaveProgress { .. }))
+            .unwrap()
+    }
+
+    #[test]
+    fn last_save_to_a_path_wins() {
+        let dir = TempDir::new("ordered");
+        let path = dir.0.join("game.sav");
+        let (sender, receiver) = mpsc::channel();
+        for bytes in [b"first".to_vec(), b"second".to_vec(), b"third".to_vec()] {
+            assert!(submit(job(&path, Request::Save(bytes), &sender)).is_ok());
+        }
+        for _ in 0..3 {
+            assert!(matches!(
+                wait_finished(&receiver),
+                JobEvent::SaveFinished { result: Ok(()), .. }
+            ));
+        }
+        assert_eq!(fs::read(&path).unwrap(), b"third");
+    }
+
+    #[test]
+    fn load_sees_earlier_save() {
+        let dir = TempDir::new("roundtrip");
+        let path = dir.0.join("game.sav");
+        let (sender, receiver) = mpsc::channel();
+        assert!(submit(job(&path, Request::Save(b"data".to_vec()), &sender)).is_ok());
+        assert!(submit(job(&path, Request::Load, &sender)).is_ok());
+        wait_finished(&receiver);
+        match wait_finished(&receiver) {
+            JobEvent::LoadFinished { files, .. } => {
+                assert_eq!(files[0].as_ref().unwrap(), b"data");
+            }
+            _ => panic!("expected the load to finish after the save"),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use godot::{
+    engine::{global::PropertyUsageFlags, Node2D, Node3D, PackedScene, ResourceLoader},
+    prelude::*,
+};
+
+use super::{game_globals::Sqore, serialization::SaveDataBuilder, tagged_json};
+
+const S_PERSIST_GROUP: &str = "persistence/group";
+
+/// The entry of the builder holding every persisted node, keyed by path. Tagged (see [tagged_json]) so transforms and typed properties survive any save format
+const KEY_NODES: &str = "scene_nodes";
+const KEY_SCENE: &str = "__scene__";
+const KEY_PARENT: &str = "__parent__";
+const KEY_NAME: &str = "__name__";
+const KEY_TRANSFORM: &str = "transform";
+const KEY_PROPERTIES: &str = "properties";
+const KEY_CUSTOM: &str = "custom";
+
+/// Optional property on a persisted node listing which properties to store. Without it, all exported script properties are stored
+const PROPERTY_PERSIST_LIST: &str = "persist_properties";
+/// Optional method on a persisted node returning extra data to store
+const METHOD_SAVE_DATA: &str = "save_data";
+/// Optional method on a persisted node receiving the data returned by `save_data`
+const METHOD_LOAD_DATA: &str = "load_data";
+
+/// Saves and restores the state of every node in the persist group (`addons/sqore/persistence/group`, "persist" by default) without each node writing its own serialization.
+///
+/// Each node is stored as a dictionary keyed by its path relative to the root, all under a single entry of the builder. Nodes that were spawned from a scene are re-instantiated on load if they are missing, and nodes that no longer existed when saving are freed.
+#[derive(GodotClass)]
+#[class(init, base=Object)]
+pub struct ScenePersistence {
+    base: Base<Object>,
+}
+
+#[godot_api]
+impl ScenePersistence {
+    #[func]
+    pub fn get_persist_group() -> StringName {
+        let group = Sqore::get_or_init_default(S_PERSIST_GROUP, "persist".to_godot())
+            .unwrap_or("persist".to_godot());
+        StringName::from(group)
+    }
+
+    /// Stores all persisted nodes under `root` into the builder
+    #[func]
+    pub fn save_scene(root: Gd<Node>, mut builder: Gd<SaveDataBuilder>) {
+        let mut nodes = Dictionary::new();
+        for node in Self::find_persisted_nodes(&root) {
+            let path = root.get_path_to(node.clone());
+            let mut data = Dictionary::new();
+            data.set(KEY_SCENE, node.get_scene_file_path());
+            data.set(KEY_NAME, GString::from(node.get_name()));
+            if let Some(parent) = node.get_parent() {
+                data.set(KEY_PARENT, root.get_path_to(parent).to_string());
+            }
+            if let Some(transform) = Self::get_transform(&node) {
+                data.set(KEY_TRANSFORM, transform);
+            }
+            let mut properties = Dictionary::new();
+            for property in Self::get_persisted_properties(&node).iter_shared() {
+                properties.set(property.clone(), node.get(StringName::from(property)));
+            }
+            data.set(KEY_PROPERTIES, properties);
+            if node.has_method(StringName::from(METHOD_SAVE_DATA)) {
+                let custom = node.clone().call(StringName::from(METHOD_SAVE_DATA), &[]);
+                data.set(KEY_CUSTOM, custom);
+            }
+            nodes.set(path.to_string(), data);
+        }
+        builder.bind_mut().set_value(
+            KEY_NODES.to_godot(),
+            tagged_json::encode_variant(&nodes.to_variant()),
+        );
+    }
+
+    /// Restores persisted nodes under `root` from the builder, re-instantiating missing nodes and freeing nodes that are not in the save
+    #[func]
+    pub fn load_scene(root: Gd<Node>, mut builder: Gd<SaveDataBuilder>) {
+        let stored = builder
+            .bind_mut()
+            .internal_get_value(KEY_NODES.to_godot(), Dictionary::new());
+        let nodes =
+            Dictionary::try_from_variant(&tagged_json::decode_variant(&stored.to_variant()))
+                .unwrap_or_default();
+        for node in Self::find_persisted_nodes(&root) {
+            let path = root.get_path_to(node.clone()).to_string();
+            if !nodes.contains_key(path) {
+                node.clone().queue_free();
+            }
+        }
+
+        // parents before children, so spawned nodes have somewhere to go
+        let mut entries: Vec<(GString, Dictionary)> = nodes
+            .iter_shared()
+            .filter_map(|(path, data)| {
+                Some((
+                    GString::try_from_variant(&path).ok()?,
+                    Dictionary::try_from_variant(&data).ok()?,
+                ))
+            })
+            .collect();
+        entries.sort_by_key(|(path, _)| path.to_string().matches('/').count());
+        for (path, data) in entries {
+            let node = match root.get_node_or_null(NodePath::from(path.clone())) {
+                Some(node) => node,
+                None => match Self::respawn_node(&root, &data) {
+                    Some(node) => node,
+                    None => {
+                        godot_warn!("Failed to restore persisted node at path: {}", path);
+                        continue;
+                    }
+                },
+            };
+            Self::restore_node(node, &data);
+        }
+    }
+
+    fn find_persisted_nodes(root: &Gd<Node>) -> Vec<Gd<Node>> {
+        let Some(mut tree) = root.get_tree() else {
+            return Vec::new();
+        };
+        tree.get_nodes_in_group(Self::get_persist_group())
+            .iter_shared()
+            .filter(|node| *node == *root || root.is_ancestor_of(node.clone()))
+            .collect()
+    }
+
+    fn get_persisted_properties(node: &Gd<Node>) -> PackedStringArray {
+        let declared = node.get(StringName::from(PROPERTY_PERSIST_LIST));
+        if let Ok(list) = PackedStringArray::try_from_variant(&declared) {
+            return list;
+        }
+        if let Ok(list) = Array::<GString>::try_from_variant(&declared) {
+            return PackedStringArray::from_iter(list.iter_shared());
+        }
+        // exported script properties
+        let required =
+            (PropertyUsageFlags::SCRIPT_VARIABLE.ord() | PropertyUsageFlags::STORAGE.ord()) as i64;
+        let mut properties = PackedStringArray::new();
+        for property in node.get_property_list().iter_shared() {
+            let usage = SaveDataBuilder::get_entry_from::<i64>(&property, "usage").unwrap_or(0);
+            if usage & required != required {
+                continue;
+            }
+            if let Some(name) = SaveDataBuilder::get_entry_from::<GString>(&property, "name") {
+                properties.push(name);
+            }
+        }
+        properties
+    }
+
+    fn get_transform(node: &Gd<Node>) -> Option<Variant> {
+        if let Ok(node3d) = node.clone().try_cast::<Node3D>() {
+            return Some(node3d.get_transform().to_variant());
+        }
+        if let Ok(node2d) = node.clone().try_cast::<Node2D>() {
+            return Some(node2d.get_transform().to_variant());
+        }
+        None
+    }
+
+    fn set_transform(node: &Gd<Node>, transform: &Variant) {
+        if let (Ok(mut node3d), Ok(transform)) = (
+            node.clone().try_cast::<Node3D>(),
+            Transform3D::try_from_variant(transform),
+        ) {
+            node3d.set_transform(transform);
+        } else if let (Ok(mut node2d), Ok(transform)) = (
+            node.clone().try_cast::<Node2D>(),
+            Transform2D::try_from_variant(transform),
+        ) {
+            node2d.set_transform(transform);
+        } else {
+            godot_warn!(
+                "Failed to restore the transform of persisted node {}",
+                node.get_name()
+            );
+        }
+    }
+
+    fn respawn_node(root: &Gd<Node>, data: &Dictionary) -> Option<Gd<Node>> {
+        let scene_path =
+            SaveDataBuilder::get_entry_from::<GString>(data, KEY_SCENE).unwrap_or_default();
+        if scene_path.is_empty() {
+            // nodes that are part of the level can't be re-created
+            return None;
+        }
+        let parent_path =
+            SaveDataBuilder::get_entry_from::<GString>(data, KEY_PARENT).unwrap_or_default();
+        let mut parent = root.get_node_or_null(NodePath::from(parent_path))?;
+        let scene = ResourceLoader::singleton()
+            .load(scene_path)?
+            .try_cast::<PackedScene>()
+            .ok()?;
+        let mut node = scene.instantiate()?;
+        let name = StringName::from(
+            SaveDataBuilder::get_entry_from::<GString>(data, KEY_NAME).unwrap_or_default(),
+        );
+        // named before entering the tree, so children saved under this path can find it
+        if !name.is_empty() {
+            node.set_name(name.clone().into());
+        }
+        node.add_to_group(Self::get_persist_group());
+        parent.add_child(node.clone());
+        if !name.is_empty() && node.get_name() != name {
+            godot_warn!(
+                "Restored node \"{}\" was renamed to \"{}\" to avoid a name collision, its saved children will not be found",
+                name,
+                node.get_name()
+            );
+        }
+        Some(node)
+    }
+
+    fn restore_node(mut node: Gd<Node>, data: &Dictionary) {
+        let properties =
+            SaveDataBuilder::get_entry_from::<Dictionary>(data, KEY_PROPERTIES).unwrap_or_default();
+        for (property, value) in properties.iter_shared() {
+            node.set(StringName::from(GString::from_variant(&property)), value);
+        }
+        if let Some(transform) = data.get(KEY_TRANSFORM) {
+            Self::set_transform(&node, &transform);
+        }
+        if let Some(custom) = data.get(KEY_CUSTOM) {
+            if !custom.is_nil() && node.has_method(StringName::from(METHOD_LOAD_DATA)) {
+                node.call(StringName::from(METHOD_LOAD_DATA), &[custom]);
+            }
+        }
+    }
+}
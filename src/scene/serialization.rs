@@ -1,17 +1,182 @@
-use std::collections::HashMap;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+};
 
 use godot::{
-    engine::{file_access::ModeFlags, DirAccess, FileAccess, Json, ProjectSettings},
+    engine::{
+        file_access::CompressionMode,
+        global::Error,
+        utilities::{bytes_to_var, var_to_bytes},
+        Json, ProjectSettings,
+    },
     prelude::*,
 };
 
+use crate::util::SqoreUtil;
+
+use super::{
+    game_globals::Sqore,
+    save_crypto::{OpenError, SaveSecurity},
+    save_io::{self, WriteFailure},
+    save_migration,
+    save_worker::{self, Job, JobEvent, Request},
+    tagged_json,
+};
+
 const INTERNAL_PREFIX: &str = "__internal__";
+const META_PREFIX: &str = "__meta__";
+const META_SCHEMA: &str = "__meta__schema";
+const META_VERSION: &str = "__meta__version";
+/// Marks files written as [SaveFormat::TaggedJson], the only JSON files whose tags are decoded
+const META_FORMAT: &str = "__meta__format";
+const FORMAT_TAGGED_JSON: &str = "tagged_json";
+const S_BACKUP_COUNT: &str = "serialization/backup_count";
+
+/// Header for data stored with `var_to_bytes`. Files without a known header are parsed as JSON
+const BINARY_HEADER: &[u8; 4] = b"SQBN";
+/// Header for compressed data, followed by the compression mode (1 byte) and the uncompressed size (u64, little endian)
+const COMPRESSED_HEADER: &[u8; 4] = b"SQCZ";
+const COMPRESSED_HEADER_LEN: usize = 4 + 1 + 8;
+/// Largest uncompressed size accepted from a header, so a corrupted or crafted file can't request a huge allocation
+const MAX_DECOMPRESSED_SIZE: u64 = 256 * 1024 * 1024;
+
+#[derive(GodotConvert, Var, Export, Default, Clone, Copy, PartialEq, Eq)]
+#[godot(via=i32)]
+pub enum SaveFormat {
+    /// Plain JSON. Loses most Variant types (vectors become strings and ints become floats)
+    Json = 0,
+    /// Godot's `var_to_bytes`. Keeps all Variant types and is smaller, but not human readable
+    Binary = 1,
+    /// JSON that keeps most Variant types by tagging them, see [tagged_json](super::tagged_json)
+    #[default]
+    TaggedJson = 2,
+}
+
+#[derive(GodotConvert, Var, Export, Default, Clone, Copy, PartialEq, Eq)]
+#[godot(via=i32)]
+pub enum SaveCompression {
+    #[default]
+    None = 0,
+    Zstd = 1,
+    Gzip = 2,
+}
+
+impl SaveCompression {
+    /// The matching `FileAccess.CompressionMode`
+    fn get_mode(&self) -> Option<CompressionMode> {
+        match self {
+            SaveCompression::None => None,
+            SaveCompression::Zstd => Some(CompressionMode::ZSTD),
+            SaveCompression::Gzip => Some(CompressionMode::GZIP),
+        }
+    }
+}
+
+/// Why a save or load failed. See `SaveDataBuilder.get_last_error`
+#[derive(GodotConvert, Var, Export, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[godot(via=i32)]
+pub enum SaveErrorCode {
+    #[default]
+    Ok = 0,
+    NotFound = 1,
+    PermissionDenied = 2,
+    /// The file is not valid JSON. The message includes the line
+    ParseError = 3,
+    /// The file was parsed but does not contain a dictionary
+    WrongRootType = 4,
+    /// The file was saved by a newer version of the game, or with a different schema
+    VersionMismatch = 5,
+    /// The file was modified outside of the game, see [save_crypto](super::save_crypto)
+    ChecksumMismatch = 6,
+    /// The file could not be decrypted or decompressed
+    Corrupted = 7,
+    WriteFailed = 8,
+}
+
+#[derive(Debug, Clone)]
+pub struct SaveError {
+    pub code: SaveErrorCode,
+    pub message: String,
+}
+
+impl SaveError {
+    pub fn new(code: SaveErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn read_code(kind: std::io::ErrorKind) -> SaveErrorCode {
+        match kind {
+            std::io::ErrorKind::NotFound => SaveErrorCode::NotFound,
+            std::io::ErrorKind::PermissionDenied => SaveErrorCode::PermissionDenied,
+            _ => SaveErrorCode::Corrupted,
+        }
+    }
+
+    fn read_failed(code: SaveErrorCode, file_path: &GString) -> Self {
+        let message = match code {
+            SaveErrorCode::NotFound => format!("File {} does not exist", file_path),
+            SaveErrorCode::PermissionDenied => {
+                format!("No permission to read file {}", file_path)
+            }
+            _ => format!("Failed to read file {}", file_path),
+        };
+        Self::new(code, message)
+    }
+
+    /// Adds the file to the message
+    fn in_file(self, file_path: &GString) -> Self {
+        Self::new(self.code, format!("{}: {}", file_path, self.message))
+    }
+
+    fn from_write_failure(failure: WriteFailure) -> Self {
+        let code = match failure.kind {
+            std::io::ErrorKind::PermissionDenied => SaveErrorCode::PermissionDenied,
+            _ => SaveErrorCode::WriteFailed,
+        };
+        Self::new(code, failure.message)
+    }
+}
+
+/// A single step that upgrades save data from one schema version to the next
+#[derive(Clone)]
+pub enum SchemaMigration {
+    Rust(fn(&mut Dictionary)),
+    /// Takes the data dictionary. May either modify it in place or return a new dictionary
+    Script(Callable),
+}
+
+thread_local! {
+    /// schema name -> (version the migration upgrades from -> migration)
+    static MIGRATIONS: RefCell<HashMap<String, BTreeMap<i64, SchemaMigration>>> = RefCell::new(HashMap::new());
+}
 
 #[derive(GodotClass)]
 #[class(base=Object)]
 pub struct SaveDataBuilder {
     data: Dictionary,
     child_builders: HashMap<GString, Gd<SaveDataBuilder>>,
+    /// Identifies which migrations apply to this data. Files saved without a schema are never migrated
+    #[var]
+    schema_name: GString,
+    /// The version stamped on save, and the version data is migrated up to on load
+    #[var]
+    schema_version: i64,
+    /// How the data is written on save. Loading detects the format automatically, so this can be changed without breaking existing files
+    #[var]
+    format: SaveFormat,
+    #[var]
+    compression: SaveCompression,
+    last_error: SaveErrorCode,
+    last_error_message: GString,
+    /// Results of `save_async` and `load_async` from the worker thread, drained every frame while jobs are pending
+    job_events: Option<(Sender<JobEvent>, Receiver<JobEvent>)>,
+    pending_jobs: usize,
     base: Base<Object>,
 }
 
@@ -21,12 +186,39 @@ impl IObject for SaveDataBuilder {
         Self {
             data: Dictionary::new(),
             child_builders: HashMap::new(),
+            schema_name: GString::new(),
+            schema_version: 0,
+            format: SaveFormat::TaggedJson,
+            compression: SaveCompression::None,
+            last_error: SaveErrorCode::Ok,
+            last_error_message: GString::new(),
+            job_events: None,
+            pending_jobs: 0,
             base,
         }
     }
 }
 #[godot_api]
 pub impl SaveDataBuilder {
+    pub const SIGNAL_SAVE_PROGRESS: &'static str = "save_progress";
+    pub const SIGNAL_SAVE_FINISHED: &'static str = "save_finished";
+    pub const SIGNAL_LOAD_FINISHED: &'static str = "load_finished";
+    pub const SIGNAL_IO_ERROR: &'static str = "io_error";
+
+    /// Progress of `save_async` from 0 to 1
+    #[signal]
+    fn save_progress(file_path: GString, progress: f32) {}
+
+    #[signal]
+    fn save_finished(file_path: GString, success: bool) {}
+
+    #[signal]
+    fn load_finished(file_path: GString, success: bool) {}
+
+    /// Emitted when `save_async` or `load_async` fail, along with the finished signal
+    #[signal]
+    fn io_error(file_path: GString, code: SaveErrorCode, message: GString) {}
+
     #[func]
     pub fn set_value(&mut self, key: GString, value: Variant) {
         self.data.set(key, value);
@@ -45,89 +237,312 @@ pub impl SaveDataBuilder {
         default
     }
 
+    #[func]
+    pub fn has_child_builder(&self, key: GString) -> bool {
+        self.child_builders.contains_key(&key)
+    }
+
+    #[func]
+    pub fn get_child_builder_keys(&self) -> Array<GString> {
+        Array::from_iter(self.child_builders.keys().cloned())
+    }
+
     #[func]
     pub fn get_child_builder(&mut self, key: GString) -> Gd<SaveDataBuilder> {
-        let mut n_builder = SaveDataBuilder::new_alloc();
-        if let Some(child) = self.child_builders.get_mut(&key) {
-            n_builder = child.clone();
-        } else {
-            self.child_builders.insert(key, n_builder.clone());
+        self.child_builders
+            .entry(key)
+            .or_insert_with(SaveDataBuilder::new_alloc)
+            .clone()
+    }
+
+    /// Frees the builder along with all of its child builders
+    pub fn free_with_children(mut builder: Gd<SaveDataBuilder>) {
+        let children: Vec<Gd<SaveDataBuilder>> = builder
+            .bind_mut()
+            .child_builders
+            .drain()
+            .map(|(_, child)| child)
+            .collect();
+        for child in children {
+            Self::free_with_children(child);
         }
-        n_builder
+        builder.free();
     }
 
+    /// Saves without ever leaving a half-written file behind. The data is written to a temporary file and verified before replacing the original, and the previous versions are kept as rotating backups (`file.bak1` being the newest).
     #[func]
     pub fn save(&mut self, file_path: GString) -> bool {
-        let abs_path = ProjectSettings::singleton().globalize_path(file_path.clone());
-        if let Some(base_dir) = std::path::Path::new(abs_path.to_string().as_str()).parent() {
-            if let Some(valid_base_dir) = base_dir.to_str() {
-                if !DirAccess::dir_exists_absolute(GString::from(valid_base_dir)) {
-                    DirAccess::make_dir_recursive_absolute(GString::from(valid_base_dir));
-                }
-            }
-        }
-        let Some(mut file) = FileAccess::open(file_path.clone(), ModeFlags::WRITE) else {
-            godot_warn!("Failed to access file {}", file_path);
-            return false;
+        let bytes = self.encode();
+        let result = Self::write_atomic(&file_path, &bytes);
+        self.set_result(result)
+    }
+
+    /// Writes the file on a worker thread. Emits `save_progress` while writing, then `save_finished`. Only the file access is asynchronous: encoding, compression, and encryption use the engine, so they still run on the main thread before this returns. Later changes to the builder are therefore not included. Requests run in the order they were made, so the last save to a file is the one kept
+    #[func]
+    pub fn save_async(&mut self, file_path: GString) {
+        let sealed = SaveSecurity::from_project_settings().seal(self.encode());
+        self.submit_job(&file_path, Request::Save(sealed));
+    }
+
+    /// Reads the file on a worker thread, then loads it like `load` and emits `load_finished`. Only the file access is asynchronous, decrypting and parsing happen on the main thread once the file was read
+    #[func]
+    pub fn load_async(&mut self, file_path: GString) {
+        self.submit_job(&file_path, Request::Load);
+    }
+
+    /// Handles the results of finished jobs. Connected to the frame signal of the scene tree while jobs are pending
+    #[func]
+    fn poll_jobs(&mut self) {
+        let events: Vec<JobEvent> = match &self.job_events {
+            Some((_, receiver)) => receiver.try_iter().collect(),
+            None => Vec::new(),
         };
-        let text = Json::stringify(self.get_as_dict().to_variant());
-        file.store_string(text);
-        true
+        for event in events {
+            self.handle_job_event(event);
+        }
+        if self.pending_jobs == 0 {
+            self.set_polling_jobs(false);
+        }
+    }
+
+    /// Why the last save or load failed, `Ok` if it succeeded
+    #[func]
+    pub fn get_last_error(&self) -> SaveErrorCode {
+        self.last_error
+    }
+
+    /// A description of the last error, suitable for logs or showing to players
+    #[func]
+    pub fn get_last_error_message(&self) -> GString {
+        self.last_error_message.clone()
     }
 
+    /// Loads the file, falling back to the newest valid backup if the file is missing or corrupted. If a schema is set, the data is migrated up to `schema_version` first
     #[func]
     pub fn load(&mut self, file_path: GString) -> bool {
-        let Some(file) = FileAccess::open(file_path.clone(), ModeFlags::READ) else {
-            godot_warn!("Failed to access file {}", file_path);
-            return false;
-        };
-        let opt_cast = Json::parse_string(file.get_as_text());
-        if let Ok(data) = Dictionary::try_from_variant(&opt_cast) {
-            for entry in data.iter_shared() {
-                let skey = GString::from_variant(&entry.0);
-                if skey.to_string().starts_with(INTERNAL_PREFIX) {
+        let result = Self::read_with_backups(&file_path);
+        self.apply_loaded(result)
+    }
+
+    fn apply_loaded(&mut self, result: Result<Dictionary, SaveError>) -> bool {
+        let result = result.and_then(|data| {
+            if self.schema_name.is_empty() {
+                return Ok(data);
+            }
+            Self::migrate(&self.schema_name.to_string(), data, self.schema_version)
+        });
+        match result {
+            Ok(data) => {
+                self.load_entries(&data);
+                self.set_result(Ok(()))
+            }
+            Err(err) => self.set_result(Err(err)),
+        }
+    }
+
+    /// Records the result as the last error, returning whether it succeeded
+    fn set_result(&mut self, result: Result<(), SaveError>) -> bool {
+        match result {
+            Ok(()) => {
+                self.last_error = SaveErrorCode::Ok;
+                self.last_error_message = GString::new();
+                true
+            }
+            Err(err) => {
+                if err.code == SaveErrorCode::ChecksumMismatch {
+                    godot_error!("{}", err.message);
                 } else {
-                    self.data.set(skey, entry.1);
+                    godot_warn!("{}", err.message);
                 }
+                self.last_error = err.code;
+                self.last_error_message = err.message.to_godot();
+                false
             }
-        };
+        }
+    }
 
-        true
+    fn emit_io_error(&mut self, file_path: &GString) {
+        let args = [
+            file_path.to_variant(),
+            self.last_error.to_variant(),
+            self.last_error_message.to_variant(),
+        ];
+        self.emit_deferred(Self::SIGNAL_IO_ERROR, &args);
     }
 
     #[func]
     pub fn load_from(dict: Dictionary) -> Gd<SaveDataBuilder> {
         let mut data = SaveDataBuilder::new_alloc();
+        data.bind_mut().load_entries(&dict);
+        data
+    }
+
+    fn load_entries(&mut self, dict: &Dictionary) {
         for entry in dict.iter_shared() {
             let skey = GString::from_variant(&entry.0);
+            if skey.to_string().starts_with(META_PREFIX) {
+                continue;
+            }
             if skey.to_string().starts_with(INTERNAL_PREFIX) {
                 // attempts to construct an internal save data builder (which is effecitively a sub-layer in the JSON)
                 if let Ok(dict) = Dictionary::try_from_variant(&entry.1) {
                     let child = SaveDataBuilder::load_from(dict);
                     let i_key = skey.to_string().replace(INTERNAL_PREFIX, "");
-                    data.bind_mut()
-                        .child_builders
-                        .insert(i_key.to_godot(), child);
+                    self.child_builders.insert(i_key.to_godot(), child);
                 } else {
                     godot_warn!("Found SaveDataBuilder entry that is corrupted. Please ensure this JSON data is correct. Key=\"{}\"; expected dictionary value. Found: {}", skey, entry.1);
                 }
             } else {
                 // loads a simple data value
-                data.bind_mut().data.set(skey, entry.1);
+                self.data.set(skey, entry.1);
             }
         }
-        data
     }
 
     pub fn try_load_file(file_path: GString) -> Option<Gd<SaveDataBuilder>> {
         let mut result = SaveDataBuilder::new_alloc();
         if !result.bind_mut().load(file_path) {
+            result.free();
+            None
+        } else {
+            Some(result)
+        }
+    }
+
+    /// Loads the file and migrates it up to the given version of the schema
+    pub fn try_load_schema(
+        file_path: GString,
+        schema_name: &str,
+        schema_version: i64,
+    ) -> Option<Gd<SaveDataBuilder>> {
+        let mut result = SaveDataBuilder::new_alloc();
+        result
+            .bind_mut()
+            .set_schema(schema_name.to_godot(), schema_version);
+        if !result.bind_mut().load(file_path) {
+            result.free();
             None
         } else {
             Some(result)
         }
     }
 
+    /// Whether the last `load` failed because the file was modified outside of the game
+    #[func]
+    pub fn was_tampered(&self) -> bool {
+        self.last_error == SaveErrorCode::ChecksumMismatch
+    }
+
+    #[func]
+    pub fn set_schema(&mut self, name: GString, version: i64) {
+        self.schema_name = name;
+        self.schema_version = version;
+    }
+
+    /// Registers a GDScript migration that upgrades data of the schema from `from_version` to `from_version + 1`. The callable receives the data dictionary and can either modify it in place or return a new dictionary
+    #[func]
+    pub fn register_migration(schema_name: GString, from_version: i64, migration: Callable) {
+        Self::register_schema_migration(
+            &schema_name.to_string(),
+            from_version,
+            SchemaMigration::Script(migration),
+        );
+    }
+
+    /// Registers a Rust migration that upgrades data of the schema from `from_version` to `from_version + 1`
+    pub fn register_rust_migration(
+        schema_name: &str,
+        from_version: i64,
+        migration: fn(&mut Dictionary),
+    ) {
+        Self::register_schema_migration(
+            schema_name,
+            from_version,
+            SchemaMigration::Rust(migration),
+        );
+    }
+
+    pub fn register_schema_migration(
+        schema_name: &str,
+        from_version: i64,
+        migration: SchemaMigration,
+    ) {
+        MIGRATIONS.with_borrow_mut(|migrations| {
+            let steps = migrations.entry(schema_name.to_string()).or_default();
+            if steps.insert(from_version, migration).is_some() {
+                godot_warn!(
+                    "Replaced migration for schema \"{}\" from version {}",
+                    schema_name,
+                    from_version
+                );
+            }
+        });
+    }
+
+    /// Gets the schema version stamped on the data. Data saved before versioning was added is version 0
+    pub fn get_data_version(data: &Dictionary) -> i64 {
+        // JSON stores all numbers as floats
+        let value = data.get_or_nil(META_VERSION);
+        if let Ok(version) = i64::try_from_variant(&value) {
+            return version;
+        }
+        f64::try_from_variant(&value)
+            .map(|version| version as i64)
+            .unwrap_or(0)
+    }
+
+    /// Runs every registered migration step between the version of the data and `target_version`. Steps without a registered migration are treated as not changing the data.
+    ///
+    /// Data from a newer version, or stamped with a different schema, is an error since it can't be migrated.
+    pub fn migrate(
+        schema_name: &str,
+        data: Dictionary,
+        target_version: i64,
+    ) -> Result<Dictionary, SaveError> {
+        if let Some(data_schema) = Self::get_entry_from::<GString>(&data, META_SCHEMA) {
+            if data_schema.to_string() != schema_name {
+                return Err(SaveError::new(
+                    SaveErrorCode::VersionMismatch,
+                    format!(
+                        "Expected data for schema \"{}\", found schema \"{}\"",
+                        schema_name, data_schema
+                    ),
+                ));
+            }
+        }
+        // cloned out so a migration can register more migrations without a double borrow
+        let steps = MIGRATIONS
+            .with_borrow(|migrations| migrations.get(schema_name).cloned().unwrap_or_default());
+        let version = Self::get_data_version(&data);
+        let apply_step = |step: &SchemaMigration, mut data: Dictionary| {
+            match step {
+                SchemaMigration::Rust(migration) => migration(&mut data),
+                SchemaMigration::Script(callable) => {
+                    let result = callable.callv(varray![data.clone()]);
+                    if let Ok(replaced) = Dictionary::try_from_variant(&result) {
+                        data = replaced;
+                    }
+                }
+            }
+            data
+        };
+        let migrated = save_migration::migrate(&steps, version, target_version, data, apply_step);
+        let mut data = match migrated {
+            Ok(data) => data,
+            Err(newer) => {
+                return Err(SaveError::new(
+                    SaveErrorCode::VersionMismatch,
+                    format!(
+                        "Data for schema \"{}\" is version {}, which is newer than the supported version {}",
+                        schema_name, newer.version, newer.target_version
+                    ),
+                ))
+            }
+        };
+        data.set(META_VERSION, target_version);
+        Ok(data)
+    }
+
     #[func]
     pub fn get_as_dict(&mut self) -> Dictionary {
         let mut dict = Dictionary::new();
@@ -157,6 +572,319 @@ pub impl SaveDataBuilder {
         valid
     }
 
+    fn encode(&mut self) -> Vec<u8> {
+        let mut dict = self.get_as_dict();
+        if !self.schema_name.is_empty() {
+            dict.set(META_SCHEMA, self.schema_name.clone());
+        }
+        dict.set(META_VERSION, self.schema_version);
+        let bytes = match self.format {
+            SaveFormat::Json => Json::stringify(dict.to_variant()).to_string().into_bytes(),
+            SaveFormat::TaggedJson => {
+                dict.set(META_FORMAT, FORMAT_TAGGED_JSON);
+                Json::stringify(tagged_json::encode_variant(&dict.to_variant()))
+                    .to_string()
+                    .into_bytes()
+            }
+            SaveFormat::Binary => {
+                let mut bytes = BINARY_HEADER.to_vec();
+                bytes.extend_from_slice(var_to_bytes(dict.to_variant()).as_slice());
+                bytes
+            }
+        };
+        let Some(mode) = self.compression.get_mode() else {
+            return bytes;
+        };
+        let compressed = PackedByteArray::from(bytes.as_slice())
+            .to_variant()
+            .call("compress", &[mode.ord().to_variant()]);
+        let Ok(compressed) = PackedByteArray::try_from_variant(&compressed) else {
+            godot_warn!("Failed to compress save data, saving uncompressed");
+            return bytes;
+        };
+        let mut result = COMPRESSED_HEADER.to_vec();
+        result.push(mode.ord() as u8);
+        result.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+        result.extend_from_slice(compressed.as_slice());
+        result
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Dictionary, SaveError> {
+        let corrupted = || SaveError::new(SaveErrorCode::Corrupted, "Failed to decompress data");
+        let wrong_root = || {
+            SaveError::new(
+                SaveErrorCode::WrongRootType,
+                "Expected the data to contain a dictionary",
+            )
+        };
+        if bytes.starts_with(COMPRESSED_HEADER) {
+            if bytes.len() < COMPRESSED_HEADER_LEN {
+                return Err(corrupted());
+            }
+            let mode = CompressionMode::try_from_ord(bytes[COMPRESSED_HEADER.len()] as i32)
+                .ok_or_else(corrupted)?;
+            let size_bytes: [u8; 8] = bytes[COMPRESSED_HEADER.len() + 1..COMPRESSED_HEADER_LEN]
+                .try_into()
+                .map_err(|_| corrupted())?;
+            let size = u64::from_le_bytes(size_bytes);
+            if size > MAX_DECOMPRESSED_SIZE {
+                return Err(SaveError::new(
+                    SaveErrorCode::Corrupted,
+                    format!(
+                        "Compressed data claims to be {} bytes, more than the limit of {}",
+                        size, MAX_DECOMPRESSED_SIZE
+                    ),
+                ));
+            }
+            let decompressed = PackedByteArray::from(&bytes[COMPRESSED_HEADER_LEN..])
+                .to_variant()
+                .call("decompress", &[size.to_variant(), mode.ord().to_variant()]);
+            let decompressed =
+                PackedByteArray::try_from_variant(&decompressed).map_err(|_| corrupted())?;
+            if decompressed.len() as u64 != size {
+                return Err(corrupted());
+            }
+            return Self::decode(decompressed.as_slice());
+        }
+        if let Some(binary) = bytes.strip_prefix(BINARY_HEADER) {
+            let value = bytes_to_var(PackedByteArray::from(binary));
+            return Dictionary::try_from_variant(&value).map_err(|_| wrong_root());
+        }
+        let text = std::str::from_utf8(bytes).map_err(|err| {
+            SaveError::new(
+                SaveErrorCode::ParseError,
+                format!("File is not valid text: {}", err),
+            )
+        })?;
+        let mut json = Json::new_gd();
+        if json.parse(text.to_godot()) != Error::OK {
+            return Err(SaveError::new(
+                SaveErrorCode::ParseError,
+                format!(
+                    "Failed to parse JSON on line {}: {}",
+                    json.get_error_line(),
+                    json.get_error_message()
+                ),
+            ));
+        }
+        let data = Dictionary::try_from_variant(&json.get_data()).map_err(|_| wrong_root())?;
+        // tags are only trusted in files written as tagged JSON, plain JSON is loaded as is
+        if Self::get_entry_from::<GString>(&data, META_FORMAT)
+            != Some(GString::from(FORMAT_TAGGED_JSON))
+        {
+            return Ok(data);
+        }
+        Dictionary::try_from_variant(&tagged_json::decode_variant(&data.to_variant()))
+            .map_err(|_| wrong_root())
+    }
+
+    fn get_backup_count() -> i32 {
+        Sqore::get_or_init_default(S_BACKUP_COUNT, 3)
+            .unwrap_or(3)
+            .max(0)
+    }
+
+    fn globalize(file_path: &GString) -> PathBuf {
+        let abs_path = ProjectSettings::singleton().globalize_path(file_path.clone());
+        PathBuf::from(abs_path.to_string())
+    }
+
+    /// Writes the bytes (encrypted and signed if enabled) to a temporary file, verifies it, then replaces the file
+    pub fn write_atomic(file_path: &GString, bytes: &[u8]) -> Result<(), SaveError> {
+        let sealed = SaveSecurity::from_project_settings().seal(bytes.to_vec());
+        Self::write_raw_atomic(file_path, &sealed)
+    }
+
+    /// Like `write_atomic`, but writes the bytes exactly as given. Used for files that are already sealed, such as those copied out of a [SaveBundle](super::save_bundle::SaveBundle)
+    pub fn write_raw_atomic(file_path: &GString, bytes: &[u8]) -> Result<(), SaveError> {
+        let abs_path = Self::globalize(file_path);
+        let backup_count = Self::get_backup_count();
+        save_io::with_file_lock(&abs_path, || {
+            save_io::write_atomic(&abs_path, bytes, backup_count, |_| ())
+        })
+        .map_err(SaveError::from_write_failure)
+    }
+
+    /// Whether the file exists, or could be recovered from one of its backups
+    pub fn exists_with_backups(file_path: &GString) -> bool {
+        save_io::exists_with_backups(&Self::globalize(file_path), Self::get_backup_count())
+    }
+
+    /// Reads the first valid file out of the primary file and its backups. A tampered file is an error rather than falling back to a backup
+    pub fn read_with_backups(file_path: &GString) -> Result<Dictionary, SaveError> {
+        let abs_path = Self::globalize(file_path);
+        let backup_count = Self::get_backup_count();
+        let files = save_io::with_file_lock(&abs_path, || {
+            save_io::read_with_backups(&abs_path, backup_count)
+        });
+        let files = files
+            .into_iter()
+            .map(|file| {
+                file.map_err(|err| {
+                    SaveError::read_failed(SaveError::read_code(err.kind()), file_path)
+                })
+            })
+            .collect();
+        Self::decode_with_backups(file_path, files)
+    }
+
+    fn decode_with_backups(
+        file_path: &GString,
+        files: Vec<Result<Vec<u8>, SaveError>>,
+    ) -> Result<Dictionary, SaveError> {
+        let security = SaveSecurity::from_project_settings();
+        let mut files = files.into_iter();
+        let primary = files
+            .next()
+            .unwrap_or_else(|| Err(SaveError::read_failed(SaveErrorCode::NotFound, file_path)));
+        let decoded = primary.and_then(|bytes| {
+            Self::decode_file(bytes, &security).map_err(|err| err.in_file(file_path))
+        });
+        let first_error = match decoded {
+            Ok(data) => return Ok(data),
+            Err(err) if err.code == SaveErrorCode::ChecksumMismatch => {
+                return Err(SaveError::new(
+                    err.code,
+                    format!(
+                        "File {} failed its integrity check, it was modified outside of the game",
+                        file_path
+                    ),
+                ))
+            }
+            Err(err) => err,
+        };
+        for (index, file) in files.enumerate() {
+            if let Ok(data) = file.and_then(|bytes| Self::decode_file(bytes, &security)) {
+                godot_warn!(
+                    "File {} is missing or corrupted, loaded backup {} instead",
+                    file_path,
+                    index + 1
+                );
+                return Ok(data);
+            }
+        }
+        Err(first_error)
+    }
+
+    fn decode_file(bytes: Vec<u8>, security: &SaveSecurity) -> Result<Dictionary, SaveError> {
+        if bytes.is_empty() {
+            return Err(SaveError::new(SaveErrorCode::Corrupted, "File is empty"));
+        }
+        let bytes = security.open(&bytes).map_err(|err| match err {
+            OpenError::Tampered => SaveError::new(
+                SaveErrorCode::ChecksumMismatch,
+                "File failed its integrity check",
+            ),
+            OpenError::Corrupted => {
+                SaveError::new(SaveErrorCode::Corrupted, "Failed to decrypt file")
+            }
+        })?;
+        Self::decode(&bytes)
+    }
+
+    fn submit_job(&mut self, file_path: &GString, request: Request) {
+        let (sender, _) = self.job_events.get_or_insert_with(mpsc::channel);
+        let job = Job {
+            file_path: file_path.to_string(),
+            abs_path: Self::globalize(file_path),
+            backup_count: Self::get_backup_count(),
+            request,
+            events: sender.clone(),
+        };
+        if self.pending_jobs == 0 {
+            self.set_polling_jobs(true);
+        }
+        self.pending_jobs += 1;
+        if let Err(job) = save_worker::submit(job) {
+            // reported like any other failure, on the next frame
+            let failure = match job.request {
+                Request::Save(_) => JobEvent::SaveFinished {
+                    file_path: job.file_path,
+                    result: Err(WriteFailure {
+                        kind: std::io::ErrorKind::Other,
+                        message: String::from("The save worker thread is not running"),
+                    }),
+                },
+                Request::Load => JobEvent::LoadFinished {
+                    file_path: job.file_path,
+                    files: vec![Err(std::io::ErrorKind::Other.into())],
+                },
+            };
+            let _ = job.events.send(failure);
+        }
+    }
+
+    fn set_polling_jobs(&mut self, polling: bool) {
+        let Some(mut tree) = SqoreUtil::get_scene_tree_global() else {
+            return;
+        };
+        let signal = StringName::from("process_frame");
+        let callable = Callable::from_object_method(&self.to_gd(), "poll_jobs");
+        if polling == tree.is_connected(signal.clone(), callable.clone()) {
+            return;
+        }
+        if polling {
+            tree.connect(signal, callable);
+        } else {
+            tree.disconnect(signal, callable);
+        }
+    }
+
+    fn handle_job_event(&mut self, event: JobEvent) {
+        match event {
+            JobEvent::SaveProgress {
+                file_path,
+                progress,
+            } => {
+                self.emit_deferred(
+                    Self::SIGNAL_SAVE_PROGRESS,
+                    &[file_path.to_variant(), progress.to_variant()],
+                );
+            }
+            JobEvent::SaveFinished { file_path, result } => {
+                self.pending_jobs = self.pending_jobs.saturating_sub(1);
+                let file_path = GString::from(file_path);
+                let success = self.set_result(result.map_err(SaveError::from_write_failure));
+                if !success {
+                    self.emit_io_error(&file_path);
+                }
+                self.emit_deferred(
+                    Self::SIGNAL_SAVE_FINISHED,
+                    &[file_path.to_variant(), success.to_variant()],
+                );
+            }
+            JobEvent::LoadFinished { file_path, files } => {
+                self.pending_jobs = self.pending_jobs.saturating_sub(1);
+                let file_path = GString::from(file_path);
+                let files = files
+                    .into_iter()
+                    .map(|file| {
+                        file.map_err(|err| {
+                            SaveError::read_failed(SaveError::read_code(err.kind()), &file_path)
+                        })
+                    })
+                    .collect();
+                let result = Self::decode_with_backups(&file_path, files);
+                let success = self.apply_loaded(result);
+                if !success {
+                    self.emit_io_error(&file_path);
+                }
+                self.emit_deferred(
+                    Self::SIGNAL_LOAD_FINISHED,
+                    &[file_path.to_variant(), success.to_variant()],
+                );
+            }
+        }
+    }
+
+    /// Emits after the current call, so listeners can use this builder again
+    fn emit_deferred(&mut self, signal: &str, args: &[Variant]) {
+        let mut call_args = vec![StringName::from(signal).to_variant()];
+        call_args.extend_from_slice(args);
+        self.base_mut()
+            .call_deferred(StringName::from("emit_signal"), &call_args);
+    }
+
     pub fn get_entry_from<T: FromGodot>(dict: &Dictionary, key: &str) -> Option<T> {
         let Some(value) = dict.get(key.to_godot()) else {
             return None;
@@ -168,8 +896,43 @@ pub impl SaveDataBuilder {
     }
 }
 
-/// Unfortunately this only can be used internally, but it grants access to serialization functions for all serializable functions
+/// Serialization for Rust types that manage their own save file. GDScript (or any other object) can instead register with `Sqore.register_serializable` to be saved along with the globals
 pub trait SqoreSerialized {
     fn serialize(&mut self);
     fn deserialize(&mut self);
 }
+
+/// Settings stored in their own file under a versioned schema. Implementors describe the file, and fill or read the builder in `SqoreSerialized`
+pub trait SettingsFile {
+    const SAVE_PATH: &'static str;
+    const SCHEMA: &'static str;
+    /// Increase when renaming or restructuring keys, and register a migration from the previous version
+    const SCHEMA_VERSION: i64;
+
+    /// Fills a new builder, then saves it with the schema stamped on it
+    fn write_settings_file(fill: impl FnOnce(&mut SaveDataBuilder)) -> bool {
+        let mut builder = SaveDataBuilder::new_alloc();
+        let saved = {
+            let mut bind = builder.bind_mut();
+            fill(&mut bind);
+            bind.set_schema(Self::SCHEMA.to_godot(), Self::SCHEMA_VERSION);
+            bind.save(Self::SAVE_PATH.to_godot())
+        };
+        builder.free();
+        saved
+    }
+
+    /// Loads the file, migrated to the current schema version, and passes it to `read`. Returns false without calling `read` if there is nothing to load
+    fn read_settings_file(read: impl FnOnce(&mut SaveDataBuilder)) -> bool {
+        let Some(mut builder) = SaveDataBuilder::try_load_schema(
+            Self::SAVE_PATH.to_godot(),
+            Self::SCHEMA,
+            Self::SCHEMA_VERSION,
+        ) else {
+            return false;
+        };
+        read(&mut builder.bind_mut());
+        builder.free();
+        true
+    }
+}
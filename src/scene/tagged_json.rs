@@ -0,0 +1,231 @@
+//! Type-preserving JSON.
+//!
+//! Plain JSON only knows about nulls, bools, floats, strings, arrays, and string-keyed dictionaries. Every other Variant is wrapped in a small tagged dictionary so that it comes back as the same type when loaded:
+//!
+//! ```json
+//! { "position": { "__type__": "Vector3", "__value__": "Vector3(1, 2, 3)" } }
+//! ```
+//!
+//! The value is written with `var_to_str`, which keeps the file readable and editable by hand. Only the built-in types in [TYPE_TAGS] are written this way, and loading checks the tag against the same table, so an edited file can't construct objects through `str_to_var`.
+//!
+//! Resources are stored as their resource path and loaded again through the [ResourceLoader], but only from `res://` paths listed in the `serialization/resource_allow_list` project setting.
+
+use godot::{
+    engine::{
+        utilities::{str_to_var, var_to_str},
+        Resource, ResourceLoader,
+    },
+    prelude::*,
+};
+
+use super::game_globals::Sqore;
+
+const TAG_TYPE: &str = "__type__";
+const TAG_VALUE: &str = "__value__";
+const TYPE_RESOURCE: &str = "Resource";
+/// Dictionaries that can't be stored as a JSON object (non-string keys, or a key that collides with the tag), stored as an array of key-value pairs
+const TYPE_DICTIONARY: &str = "Dictionary";
+/// Paths (or folders ending in `/`) under `res://` that saved resources may be loaded from
+const S_RESOURCE_ALLOW_LIST: &str = "serialization/resource_allow_list";
+
+/// The built-in types stored through `var_to_str`, with the tag each is written under. Objects, RIDs, callables, and signals can't be saved meaningfully and are left out on purpose
+const TYPE_TAGS: [(VariantType, &str); 28] = [
+    (VariantType::Int, "Int"),
+    (VariantType::Vector2, "Vector2"),
+    (VariantType::Vector2i, "Vector2i"),
+    (VariantType::Rect2, "Rect2"),
+    (VariantType::Rect2i, "Rect2i"),
+    (VariantType::Vector3, "Vector3"),
+    (VariantType::Vector3i, "Vector3i"),
+    (VariantType::Transform2D, "Transform2D"),
+    (VariantType::Vector4, "Vector4"),
+    (VariantType::Vector4i, "Vector4i"),
+    (VariantType::Plane, "Plane"),
+    (VariantType::Quaternion, "Quaternion"),
+    (VariantType::Aabb, "Aabb"),
+    (VariantType::Basis, "Basis"),
+    (VariantType::Transform3D, "Transform3D"),
+    (VariantType::Projection, "Projection"),
+    (VariantType::Color, "Color"),
+    (VariantType::StringName, "StringName"),
+    (VariantType::NodePath, "NodePath"),
+    (VariantType::PackedByteArray, "PackedByteArray"),
+    (VariantType::PackedInt32Array, "PackedInt32Array"),
+    (VariantType::PackedInt64Array, "PackedInt64Array"),
+    (VariantType::PackedFloat32Array, "PackedFloat32Array"),
+    (VariantType::PackedFloat64Array, "PackedFloat64Array"),
+    (VariantType::PackedStringArray, "PackedStringArray"),
+    (VariantType::PackedVector2Array, "PackedVector2Array"),
+    (VariantType::PackedVector3Array, "PackedVector3Array"),
+    (VariantType::PackedColorArray, "PackedColorArray"),
+];
+
+fn get_type_tag(variant_type: VariantType) -> Option<&'static str> {
+    TYPE_TAGS
+        .iter()
+        .find(|(tagged, _)| *tagged == variant_type)
+        .map(|(_, tag)| *tag)
+}
+
+fn get_tagged_type(tag: &str) -> Option<VariantType> {
+    TYPE_TAGS
+        .iter()
+        .find(|(_, name)| *name == tag)
+        .map(|(tagged, _)| *tagged)
+}
+
+/// Converts the value into something `Json::stringify` can write without losing type information
+pub fn encode_variant(value: &Variant) -> Variant {
+    match value.get_type() {
+        VariantType::Nil | VariantType::Bool | VariantType::Float | VariantType::String => {
+            value.clone()
+        }
+        VariantType::Array => {
+            let array = VariantArray::from_variant(value);
+            Array::from_iter(array.iter_shared().map(|entry| encode_variant(&entry))).to_variant()
+        }
+        VariantType::Dictionary => encode_dictionary(&Dictionary::from_variant(value)),
+        VariantType::Object => {
+            let Ok(resource) = Gd::<Resource>::try_from_variant(value) else {
+                godot_warn!("Cannot save objects that are not resources, saving null instead");
+                return Variant::nil();
+            };
+            let path = resource.get_path();
+            if path.is_empty() {
+                godot_warn!("Cannot save a resource that has no path, saving null instead");
+                return Variant::nil();
+            }
+            make_tag(TYPE_RESOURCE, path.to_variant())
+        }
+        other => {
+            let Some(tag) = get_type_tag(other) else {
+                godot_warn!(
+                    "Cannot save values of type {:?}, saving null instead",
+                    other
+                );
+                return Variant::nil();
+            };
+            make_tag(tag, var_to_str(value.clone()).to_variant())
+        }
+    }
+}
+
+/// Reverses [encode_variant] on parsed JSON
+pub fn decode_variant(value: &Variant) -> Variant {
+    match value.get_type() {
+        VariantType::Array => {
+            let array = VariantArray::from_variant(value);
+            Array::from_iter(array.iter_shared().map(|entry| decode_variant(&entry))).to_variant()
+        }
+        VariantType::Dictionary => decode_dictionary(&Dictionary::from_variant(value)),
+        _ => value.clone(),
+    }
+}
+
+fn make_tag(type_name: &str, value: Variant) -> Variant {
+    let mut tag = Dictionary::new();
+    tag.set(TAG_TYPE, type_name);
+    tag.set(TAG_VALUE, value);
+    tag.to_variant()
+}
+
+fn encode_dictionary(dict: &Dictionary) -> Variant {
+    let json_compatible = dict.keys_array().iter_shared().all(|key| {
+        key.get_type() == VariantType::String && key.to::<GString>().to_string() != TAG_TYPE
+    });
+    if json_compatible {
+        let mut encoded = Dictionary::new();
+        for (key, entry) in dict.iter_shared() {
+            encoded.set(key, encode_variant(&entry));
+        }
+        return encoded.to_variant();
+    }
+    let pairs =
+        Array::from_iter(dict.iter_shared().map(|(key, entry)| {
+            varray![encode_variant(&key), encode_variant(&entry)].to_variant()
+        }));
+    make_tag(TYPE_DICTIONARY, pairs.to_variant())
+}
+
+fn decode_dictionary(dict: &Dictionary) -> Variant {
+    let Some(type_name) = dict.get(TAG_TYPE) else {
+        let mut decoded = Dictionary::new();
+        for (key, entry) in dict.iter_shared() {
+            decoded.set(key, decode_variant(&entry));
+        }
+        return decoded.to_variant();
+    };
+    let value = dict.get_or_nil(TAG_VALUE);
+    match type_name.to::<GString>().to_string().as_str() {
+        TYPE_RESOURCE => {
+            let path = GString::try_from_variant(&value).unwrap_or_default();
+            if !is_resource_allowed(&path.to_string()) {
+                godot_warn!(
+                    "Refusing to load saved resource \"{}\", it is not in the resource allow list",
+                    path
+                );
+                return Variant::nil();
+            }
+            match ResourceLoader::singleton().load(path.clone()) {
+                Some(resource) => resource.to_variant(),
+                None => {
+                    godot_warn!("Failed to load saved resource: {}", path);
+                    Variant::nil()
+                }
+            }
+        }
+        TYPE_DICTIONARY => {
+            let mut decoded = Dictionary::new();
+            let pairs = VariantArray::try_from_variant(&value).unwrap_or_default();
+            for pair in pairs.iter_shared() {
+                let pair = VariantArray::try_from_variant(&pair).unwrap_or_default();
+                if pair.len() != 2 {
+                    continue;
+                }
+                decoded.set(decode_variant(&pair.get(0)), decode_variant(&pair.get(1)));
+            }
+            decoded.to_variant()
+        }
+        tag => decode_builtin(tag, &value),
+    }
+}
+
+/// Parses a value written with `var_to_str`, as long as it is the built-in type the tag names
+fn decode_builtin(tag: &str, value: &Variant) -> Variant {
+    let Some(expected) = get_tagged_type(tag) else {
+        godot_warn!(
+            "Unknown type \"{}\" in saved data, loading null instead",
+            tag
+        );
+        return Variant::nil();
+    };
+    let text = GString::try_from_variant(value).unwrap_or_default();
+    // str_to_var can construct objects with this syntax, which must never come from a save file
+    let as_string = text.to_string();
+    if as_string.contains("Object(") || as_string.contains("Resource(") {
+        godot_warn!("Saved {} contains an object, loading null instead", tag);
+        return Variant::nil();
+    }
+    let decoded = str_to_var(text);
+    if decoded.get_type() != expected {
+        godot_warn!("Saved value is not a valid {}, loading null instead", tag);
+        return Variant::nil();
+    }
+    decoded
+}
+
+fn is_resource_allowed(path: &str) -> bool {
+    if !path.starts_with("res://") || path.contains("..") {
+        return false;
+    }
+    let allowed = Sqore::get_or_init_default(S_RESOURCE_ALLOW_LIST, PackedStringArray::new())
+        .unwrap_or_default();
+    allowed.as_slice().iter().any(|entry| {
+        let entry = entry.to_string();
+        if entry.ends_with('/') {
+            path.starts_with(&entry)
+        } else {
+            path == entry
+        }
+    })
+}
@@ -3,12 +3,23 @@ use godot::{
     prelude::*,
 };
 
+use crate::scene::input::{
+    input_device::{InputDeviceClass, InputDeviceMonitor},
+    input_labels::JoypadStyle,
+};
+
 type Sfx = Option<Gd<AudioStream>>;
 #[derive(GodotClass)]
 #[class(init, base=Node)]
-struct GuiInteract {
+pub struct GuiInteract {
     #[export]
     auto_focus: bool,
+    /// grabs focus when the mouse enters the parent, without grabbing focus on ready like `auto_focus`
+    #[export]
+    focus_on_hover: bool,
+    /// only applies `auto_focus` while the player is using a joypad, and grabs focus when they switch to one. Mouse players don't need a focused control highlighted
+    #[export]
+    auto_focus_joypad_only: bool,
     #[export]
     hover_sfx: Sfx,
     #[export]
@@ -43,7 +54,16 @@ impl INode for GuiInteract {
 
         // grab focus
         if self.auto_focus {
-            control_parent.grab_focus();
+            let mut monitor = InputDeviceMonitor::singleton();
+            if !self.auto_focus_joypad_only || monitor.bind_mut().is_using_joypad() {
+                control_parent.grab_focus();
+            }
+            if self.auto_focus_joypad_only {
+                monitor.connect(
+                    StringName::from(InputDeviceMonitor::SIGNAL_INPUT_DEVICE_CHANGED),
+                    Callable::from_object_method(&self.to_gd(), "on_input_device_changed"),
+                );
+            }
         }
 
         // connect signals
@@ -88,7 +108,28 @@ impl GuiInteract {
         };
 
         // grab focus
-        if self.auto_focus {
+        if self.auto_focus || self.focus_on_hover {
+            control_parent.grab_focus();
+        }
+    }
+
+    #[func]
+    fn on_input_device_changed(&mut self, device: InputDeviceClass, _style: JoypadStyle) {
+        if device != InputDeviceClass::Joypad {
+            return;
+        }
+        let Some(node_parent) = self.base().get_parent() else {
+            return;
+        };
+        let Ok(mut control_parent) = node_parent.try_cast::<Control>() else {
+            return;
+        };
+        // don't steal focus from another control the player is already on
+        let has_focus_owner = control_parent
+            .get_viewport()
+            .and_then(|viewport| viewport.gui_get_focus_owner())
+            .is_some();
+        if control_parent.is_visible_in_tree() && !has_focus_owner {
             control_parent.grab_focus();
         }
     }
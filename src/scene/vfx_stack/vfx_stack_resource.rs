@@ -1,6 +1,6 @@
 use godot::{engine::ShaderMaterial, prelude::*};
 
-use crate::scene::game_globals::Sqore;
+use crate::scene::{game_globals::Sqore, serialization::SaveDataBuilder};
 
 #[derive(GodotClass)]
 #[class(init, base=Resource)]
@@ -52,6 +52,25 @@ impl VFXStack {
         }
         Sqore::singleton().emit_signal(StringName::from(Sqore::SIGNAL_VFX_STACK_CHANGED), &[]);
     }
+
+    /// Stores which layers are enabled, keyed by layer label. The stack is saved as a participant of `Sqore`
+    #[func]
+    pub fn serialize(&self, mut builder: Gd<SaveDataBuilder>) {
+        let mut sbind = builder.bind_mut();
+        for layer in self.layers.iter_shared() {
+            let layer = layer.bind();
+            sbind.set_value(layer.label.clone(), layer.enabled.to_variant());
+        }
+    }
+
+    #[func]
+    pub fn deserialize(&mut self, mut builder: Gd<SaveDataBuilder>) {
+        let mut sbind = builder.bind_mut();
+        for mut layer in self.layers.iter_shared() {
+            let mut layer = layer.bind_mut();
+            layer.enabled = sbind.internal_get_value(layer.label.clone(), layer.enabled);
+        }
+    }
 }
 
 #[derive(GodotClass)]
@@ -1,5 +1,5 @@
 use godot::engine::tween::{EaseType, TransitionType};
-use godot::engine::{Engine, SceneTree, Tween};
+use godot::engine::{DisplayServer, Engine, SceneTree, Tween, Viewport};
 use godot::prelude::*;
 
 pub struct SqoreUtil;
@@ -29,4 +29,49 @@ impl SqoreUtil {
             .set_ease(ease.unwrap_or(EaseType::IN_OUT))?
             .set_trans(trans.unwrap_or(TransitionType::LINEAR))
     }
+
+    /// Converts a fraction of `size` (such as a viewport width) to pixels, clamped between `min` and `max`
+    pub fn percent_to_pixels(size: f32, percent: f32, min: i32, max: i32) -> i32 {
+        ((size * percent).round() as i32).clamp(min, max.max(min))
+    }
+
+    /// Jumps a tween to its end in a single step and stops it, so infinite loops can't hang the caller
+    pub fn finish_tween(tween: &mut Gd<Tween>) {
+        tween.custom_step(f64::MAX);
+        tween.kill();
+    }
+
+    /// Finds how far each edge of the viewport (left, top, right, bottom) must be inset to stay within the display's safe area. Values are in viewport pixels.
+    ///
+    /// Only the root window maps onto the display, so any other viewport (such as a SubViewport for split-screen) gets no insets.
+    pub fn get_safe_area_insets(viewport: &Gd<Viewport>) -> (f32, f32, f32, f32) {
+        let is_root = Self::get_scene_tree_global()
+            .and_then(|tree| tree.get_root())
+            .is_some_and(|root| root.instance_id() == viewport.instance_id());
+        if !is_root {
+            return (0f32, 0f32, 0f32, 0f32);
+        }
+        let display = DisplayServer::singleton();
+        let safe = display.get_display_safe_area();
+        let window_pos = display.window_get_position();
+        let window_size = display.window_get_size();
+        if window_size.x <= 0 || window_size.y <= 0 {
+            return (0f32, 0f32, 0f32, 0f32);
+        }
+        // the viewport may be scaled relative to the window by the stretch mode
+        let view_size = viewport.get_visible_rect().size;
+        let scale_x = view_size.x / window_size.x as f32;
+        let scale_y = view_size.y / window_size.y as f32;
+
+        let left = (safe.position.x - window_pos.x).max(0);
+        let top = (safe.position.y - window_pos.y).max(0);
+        let right = ((window_pos.x + window_size.x) - (safe.position.x + safe.size.x)).max(0);
+        let bottom = ((window_pos.y + window_size.y) - (safe.position.y + safe.size.y)).max(0);
+        (
+            left as f32 * scale_x,
+            top as f32 * scale_y,
+            right as f32 * scale_x,
+            bottom as f32 * scale_y,
+        )
+    }
 }